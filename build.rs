@@ -0,0 +1,31 @@
+// writes a man page for eggsecutor and each of its subcommands, from the same
+// clap definitions the binary parses with, to $OUT_DIR/man and to
+// $EGGSECUTOR_MAN_DIR when that's set, e.g. for packaging
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[allow(dead_code)]
+#[path = "src/subcommands.rs"]
+mod subcommands;
+
+#[allow(dead_code)]
+#[path = "src/man.rs"]
+mod man;
+
+const MAN_DIR_ENV_KEY: &str = "EGGSECUTOR_MAN_DIR";
+
+fn main() -> io::Result<()> {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/subcommands.rs");
+    println!("cargo:rerun-if-changed=src/man.rs");
+    println!("cargo:rerun-if-env-changed={}", MAN_DIR_ENV_KEY);
+
+    let app = subcommands::get_app();
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("cargo sets OUT_DIR"));
+    man::write_all_man_pages(&app, &out_dir.join("man"))?;
+    if let Some(man_dir) = env::var_os(MAN_DIR_ENV_KEY) {
+        man::write_all_man_pages(&app, Path::new(&man_dir))?;
+    }
+    Ok(())
+}
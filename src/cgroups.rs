@@ -0,0 +1,334 @@
+use super::config;
+use super::limits;
+use super::output_display;
+use serde::{Deserialize, Serialize};
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CGROUP_DIR_NAME: &str = "eggsecutor";
+const CPU_MAX_PERIOD_USEC: u64 = 100_000;
+// how long a stopped process gets to exit on its own before its whole cgroup is killed
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(5);
+const KILL_TIMEOUT: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// kept in the launch spec, unset values are left at the kernel's "max"
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CgroupLimits {
+    #[serde(default)]
+    pub memory_max: Option<u64>,
+    // in CPUs, e.g. 0.5 for half of one
+    #[serde(default)]
+    pub cpu_max: Option<f64>,
+    #[serde(default)]
+    pub pids_max: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct CgroupUsage {
+    pub memory_bytes: Option<u64>,
+    pub cpu_usec: Option<u64>,
+    pub process_count: usize,
+}
+
+impl CgroupLimits {
+    pub fn parse_memory_max(value: &str) -> Result<u64, String> {
+        limits::parse_size(value)
+    }
+
+    pub fn parse_cpu_max(value: &str) -> Result<f64, String> {
+        value
+            .parse::<f64>()
+            .ok()
+            .filter(|x| x.is_finite() && *x > 0.0)
+            .ok_or_else(|| format!(r#"invalid CPU count "{}", expected e.g. 0.5 or 2"#, value))
+    }
+
+    pub fn parse_pids_max(value: &str) -> Result<u64, String> {
+        value
+            .parse::<u64>()
+            .ok()
+            .filter(|x| *x > 0)
+            .ok_or_else(|| format!(r#"invalid process count "{}""#, value))
+    }
+
+    // (controller, interface file, value) for every limit that is set
+    fn get_interface_values(&self) -> Vec<(&'static str, &'static str, String)> {
+        let mut values = vec![];
+        if let Some(memory_max) = self.memory_max {
+            values.push(("memory", "memory.max", memory_max.to_string()));
+        }
+        if let Some(cpu_max) = self.cpu_max {
+            let quota = (cpu_max * CPU_MAX_PERIOD_USEC as f64).round() as u64;
+            let value = format!("{} {}", quota.max(1), CPU_MAX_PERIOD_USEC);
+            values.push(("cpu", "cpu.max", value));
+        }
+        if let Some(pids_max) = self.pids_max {
+            values.push(("pids", "pids.max", pids_max.to_string()));
+        }
+        values
+    }
+}
+
+impl CgroupUsage {
+    pub fn describe(&self) -> String {
+        let mut parts = vec![];
+        if let Some(memory_bytes) = self.memory_bytes {
+            parts.push(format!(
+                "mem {}",
+                output_display::get_size_string(memory_bytes)
+            ));
+        }
+        if let Some(cpu_usec) = self.cpu_usec {
+            parts.push(format!("cpu {:.1}s", cpu_usec as f64 / 1_000_000.0));
+        }
+        parts.push(format!("{} procs", self.process_count));
+        parts.join(", ")
+    }
+}
+
+// the returned path is where the process should be placed, the warnings are
+// limits that couldn't be applied because their controller isn't delegated
+pub fn create_process_cgroup(
+    process_id: &str,
+    limits: Option<&CgroupLimits>,
+) -> Result<(String, Vec<String>), String> {
+    let root = get_cgroup_root()?;
+    let cgroup = root.join(process_id);
+    fs::create_dir_all(&cgroup).map_err(|err| {
+        format!(
+            r#"could not create cgroup "{}", details: {}"#,
+            cgroup.display(),
+            err
+        )
+    })?;
+
+    let mut warnings = vec![];
+    let interface_values = limits.map(|x| x.get_interface_values()).unwrap_or_default();
+    for (controller, file_name, value) in interface_values {
+        enable_controller(&root, controller);
+        if let Err(err) = fs::write(cgroup.join(file_name), &value) {
+            warnings.push(format!(
+                "{} was not set, the {} controller may not be delegated ({})",
+                file_name, controller, err
+            ));
+        }
+    }
+    Ok((cgroup.to_string_lossy().to_string(), warnings))
+}
+
+pub fn get_procs_file_path(cgroup: &str) -> CString {
+    let procs_path = Path::new(cgroup).join("cgroup.procs");
+    CString::new(procs_path.to_string_lossy().as_bytes()).unwrap_or_default()
+}
+
+// runs in the child between fork and exec, writing "0" moves the writer itself
+pub fn join_cgroup(procs_path: &CString) -> io::Result<()> {
+    let fd = unsafe { libc::open(procs_path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let written = unsafe { libc::write(fd, b"0".as_ptr() as *const libc::c_void, 1) };
+    let err = io::Error::last_os_error();
+    unsafe { libc::close(fd) };
+    match written {
+        1 => Ok(()),
+        _ => Err(err),
+    }
+}
+
+pub fn contains_process(cgroup: &str, pid: &str) -> bool {
+    get_cgroup_pids(cgroup).iter().any(|x| x == pid)
+}
+
+// gives the main process the grace period to exit after its stop signal, then
+// kills whatever is left in the cgroup, e.g. children it left behind
+pub fn stop_cgroup(cgroup: &str, pid: &str) -> io::Result<()> {
+    if !Path::new(cgroup).exists() {
+        return Ok(());
+    }
+
+    wait_for(STOP_GRACE_PERIOD, || !contains_process(cgroup, pid));
+    if !get_cgroup_pids(cgroup).is_empty() {
+        kill_cgroup(cgroup)?;
+        wait_for(KILL_TIMEOUT, || get_cgroup_pids(cgroup).is_empty());
+    }
+    remove_cgroup(cgroup)
+}
+
+pub fn remove_cgroup(cgroup: &str) -> io::Result<()> {
+    match fs::remove_dir(cgroup) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+pub fn read_usage(cgroup: &str) -> Option<CgroupUsage> {
+    let cgroup = Path::new(cgroup);
+    if !cgroup.exists() {
+        return None;
+    }
+
+    let read_file = |file_name: &str| fs::read_to_string(cgroup.join(file_name)).ok();
+    Some(CgroupUsage {
+        memory_bytes: read_file("memory.current").and_then(|x| x.trim().parse().ok()),
+        cpu_usec: read_file("cpu.stat").and_then(|x| parse_cpu_usage_usec(&x)),
+        process_count: get_cgroup_pids(&cgroup.to_string_lossy()).len(),
+    })
+}
+
+fn get_cgroup_root() -> Result<PathBuf, String> {
+    if let Some(cgroup_root) = config::load_config().cgroup_root {
+        return Ok(PathBuf::from(shellexpand::tilde(&cgroup_root).to_string()));
+    }
+
+    let mountinfo = fs::read_to_string("/proc/self/mountinfo").map_err(|err| err.to_string())?;
+    let mount_point = parse_cgroup2_mount_point(&mountinfo)
+        .ok_or_else(|| "no cgroup v2 filesystem is mounted".to_string())?;
+    let own_cgroups = fs::read_to_string("/proc/self/cgroup").map_err(|err| err.to_string())?;
+    let own_cgroup = parse_own_cgroup_path(&own_cgroups)
+        .ok_or_else(|| "could not find the current cgroup".to_string())?;
+
+    Ok(Path::new(&mount_point)
+        .join(own_cgroup.trim_start_matches('/'))
+        .join(CGROUP_DIR_NAME))
+}
+
+// best effort, limits whose controller isn't enabled are reported when written
+fn enable_controller(root: &Path, controller: &str) {
+    let enable = format!("+{}", controller);
+    for cgroup in [root.parent(), Some(root)].iter().flatten() {
+        let _result = fs::write(cgroup.join("cgroup.subtree_control"), &enable);
+    }
+}
+
+fn kill_cgroup(cgroup: &str) -> io::Result<()> {
+    let kill_file = Path::new(cgroup).join("cgroup.kill");
+    if kill_file.exists() {
+        return fs::write(kill_file, "1");
+    }
+
+    // cgroup.kill is only available since Linux 5.14
+    for pid in get_cgroup_pids(cgroup) {
+        if let Ok(pid) = pid.parse::<libc::pid_t>() {
+            unsafe { libc::kill(pid, libc::SIGKILL) };
+        }
+    }
+    Ok(())
+}
+
+fn get_cgroup_pids(cgroup: &str) -> Vec<String> {
+    fs::read_to_string(Path::new(cgroup).join("cgroup.procs"))
+        .map(|x| x.lines().map(|x| x.to_string()).collect())
+        .unwrap_or_default()
+}
+
+fn wait_for(timeout: Duration, is_done: impl Fn() -> bool) {
+    let started_at = Instant::now();
+    while !is_done() && started_at.elapsed() < timeout {
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn parse_cgroup2_mount_point(mountinfo: &str) -> Option<String> {
+    mountinfo.lines().find_map(|line| {
+        let (mount_fields, fs_fields) = line.split_once(" - ")?;
+        match fs_fields.split_whitespace().next() {
+            Some("cgroup2") => mount_fields
+                .split_whitespace()
+                .nth(4)
+                .map(|x| x.to_string()),
+            _ => None,
+        }
+    })
+}
+
+// the cgroup v2 entry is the one with hierarchy id 0 and no controllers
+fn parse_own_cgroup_path(proc_cgroup: &str) -> Option<String> {
+    proc_cgroup
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|x| x.to_string())
+}
+
+fn parse_cpu_usage_usec(cpu_stat: &str) -> Option<u64> {
+    cpu_stat
+        .lines()
+        .find_map(|line| line.strip_prefix("usage_usec "))
+        .and_then(|x| x.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cgroup2_mount_point_should_be_found_in_hybrid_setups() {
+        let mountinfo = "\
+            35 25 0:30 / /sys/fs/cgroup ro,nosuid - tmpfs tmpfs ro,mode=755\n\
+            36 35 0:31 / /sys/fs/cgroup/memory rw,relatime - cgroup cgroup rw,memory\n\
+            42 35 0:38 / /sys/fs/cgroup/unified rw,relatime - cgroup2 cgroup2 rw\n";
+
+        assert_eq!(
+            parse_cgroup2_mount_point(mountinfo).as_deref(),
+            Some("/sys/fs/cgroup/unified")
+        );
+        assert_eq!(parse_cgroup2_mount_point(""), None);
+    }
+
+    #[test]
+    fn own_cgroup_path_should_come_from_the_v2_entry() {
+        let proc_cgroup =
+            "4:memory:/system.slice\n0::/user.slice/user-1000.slice/session-2.scope\n";
+
+        assert_eq!(
+            parse_own_cgroup_path(proc_cgroup).as_deref(),
+            Some("/user.slice/user-1000.slice/session-2.scope")
+        );
+        assert_eq!(parse_own_cgroup_path("4:memory:/system.slice\n"), None);
+    }
+
+    #[test]
+    fn limits_should_map_to_interface_files() {
+        let limits = CgroupLimits {
+            memory_max: Some(CgroupLimits::parse_memory_max("512M").unwrap()),
+            cpu_max: Some(CgroupLimits::parse_cpu_max("0.5").unwrap()),
+            pids_max: Some(CgroupLimits::parse_pids_max("64").unwrap()),
+        };
+
+        assert_eq!(
+            limits.get_interface_values(),
+            vec![
+                ("memory", "memory.max", "536870912".to_string()),
+                ("cpu", "cpu.max", "50000 100000".to_string()),
+                ("pids", "pids.max", "64".to_string()),
+            ]
+        );
+        assert!(CgroupLimits::default().get_interface_values().is_empty());
+    }
+
+    #[test]
+    fn invalid_limits_should_be_err() {
+        assert!(CgroupLimits::parse_memory_max("lots").is_err());
+        assert!(CgroupLimits::parse_cpu_max("0").is_err());
+        assert!(CgroupLimits::parse_cpu_max("half").is_err());
+        assert!(CgroupLimits::parse_pids_max("0").is_err());
+    }
+
+    #[test]
+    fn usage_should_describe_available_accounting() {
+        let cpu_stat = "usage_usec 1500000\nuser_usec 1000000\nsystem_usec 500000\n";
+        let usage = CgroupUsage {
+            memory_bytes: Some(12 * 1024 * 1024 + 300 * 1024),
+            cpu_usec: parse_cpu_usage_usec(cpu_stat),
+            process_count: 3,
+        };
+
+        assert_eq!(usage.describe(), "mem 12.3M, cpu 1.5s, 3 procs");
+        assert_eq!(CgroupUsage::default().describe(), "0 procs");
+    }
+}
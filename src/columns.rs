@@ -0,0 +1,309 @@
+use super::cgroups::{self, CgroupUsage};
+use super::labels;
+use super::output_display;
+use super::proc_stats::{self, ProcessStats};
+use super::ProcessInfo;
+use serde::Serialize;
+use std::cmp::Ordering;
+
+// e.g. "label:env" shows the value of each process's "env" label
+const LABEL_COLUMN_PREFIX: &str = "label:";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Name,
+    Id,
+    Pid,
+    Status,
+    Uptime,
+    Cpu,
+    Rss,
+    Threads,
+    Fds,
+    Cgroup,
+    Labels,
+    Label(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortOrder {
+    pub column: Column,
+    pub descending: bool,
+}
+
+// a tracked process along with everything read about it for display
+#[derive(Serialize, Debug, Clone)]
+pub struct ProcessRow {
+    #[serde(flatten)]
+    pub process: ProcessInfo,
+    pub stats: Option<ProcessStats>,
+    pub cgroup_usage: Option<CgroupUsage>,
+}
+
+impl Column {
+    // every column but the ones for a single label, which are named by their key
+    pub const ALL: [Column; 11] = [
+        Column::Name,
+        Column::Id,
+        Column::Pid,
+        Column::Status,
+        Column::Uptime,
+        Column::Cpu,
+        Column::Rss,
+        Column::Threads,
+        Column::Fds,
+        Column::Cgroup,
+        Column::Labels,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Id => "id",
+            Column::Pid => "pid",
+            Column::Status => "status",
+            Column::Uptime => "uptime",
+            Column::Cpu => "cpu",
+            Column::Rss => "rss",
+            Column::Threads => "threads",
+            Column::Fds => "fds",
+            Column::Cgroup => "cgroup",
+            Column::Labels => "labels",
+            Column::Label(_) => "label",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.strip_prefix(LABEL_COLUMN_PREFIX) {
+            Some(key) if labels::is_valid_key(key) => Some(Column::Label(key.to_string())),
+            Some(_) => None,
+            None => Column::ALL.iter().find(|x| x.get_name() == name).cloned(),
+        }
+    }
+
+    pub fn get_header(&self) -> String {
+        match self {
+            Column::Name => "Process name".to_string(),
+            Column::Cpu => "cpu%".to_string(),
+            Column::Label(key) => key.clone(),
+            column => column.get_name().to_string(),
+        }
+    }
+
+    // how narrow the column may get to fit the terminal, the others are never cut
+    pub fn get_min_width(&self) -> Option<usize> {
+        match self {
+            Column::Name | Column::Cgroup | Column::Labels | Column::Label(_) => Some(8),
+            _ => None,
+        }
+    }
+
+    // "-" where nothing could be read, e.g. another user's fds
+    pub fn get_cell_string(&self, row: &ProcessRow) -> String {
+        let stats = row.stats.as_ref();
+        let cell = match self {
+            Column::Name => Some(row.process.name.clone()),
+            Column::Id => Some(row.process.id.clone()),
+            Column::Pid => Some(row.process.pid.clone()),
+            Column::Status => Some(format!("{:?}", row.process.status)),
+            Column::Uptime => stats.map(|x| output_display::get_duration_string(x.uptime_secs)),
+            Column::Cpu => stats.map(|x| format!("{:.1}", x.cpu_percent)),
+            Column::Rss => stats.map(|x| output_display::get_size_string(x.rss_bytes)),
+            Column::Threads => stats.map(|x| x.threads.to_string()),
+            Column::Fds => stats.and_then(|x| x.open_fds).map(|x| x.to_string()),
+            Column::Cgroup => row.cgroup_usage.as_ref().map(|x| x.describe()),
+            Column::Labels => Some(labels::get_labels_string(&row.process.labels)),
+            Column::Label(key) => row.process.labels.get(key).cloned(),
+        };
+        cell.unwrap_or_else(|| "-".to_string())
+    }
+
+    fn compare(&self, a: &ProcessRow, b: &ProcessRow) -> Ordering {
+        let (a_stats, b_stats) = (a.stats.as_ref(), b.stats.as_ref());
+        match self {
+            Column::Name => a.process.name.cmp(&b.process.name),
+            Column::Id => a.process.id.cmp(&b.process.id),
+            Column::Pid => get_pid_number(a).cmp(&get_pid_number(b)),
+            Column::Status => {
+                format!("{:?}", a.process.status).cmp(&format!("{:?}", b.process.status))
+            }
+            Column::Uptime => (a_stats.map(|x| x.uptime_secs)).cmp(&b_stats.map(|x| x.uptime_secs)),
+            Column::Cpu => (a_stats.map(|x| x.cpu_percent))
+                .partial_cmp(&b_stats.map(|x| x.cpu_percent))
+                .unwrap_or(Ordering::Equal),
+            Column::Rss => (a_stats.map(|x| x.rss_bytes)).cmp(&b_stats.map(|x| x.rss_bytes)),
+            Column::Threads => (a_stats.map(|x| x.threads)).cmp(&b_stats.map(|x| x.threads)),
+            Column::Fds => {
+                (a_stats.and_then(|x| x.open_fds)).cmp(&b_stats.and_then(|x| x.open_fds))
+            }
+            Column::Cgroup => (a.cgroup_usage.as_ref().and_then(|x| x.memory_bytes))
+                .cmp(&b.cgroup_usage.as_ref().and_then(|x| x.memory_bytes)),
+            Column::Labels => labels::get_labels_string(&a.process.labels)
+                .cmp(&labels::get_labels_string(&b.process.labels)),
+            Column::Label(key) => a.process.labels.get(key).cmp(&b.process.labels.get(key)),
+        }
+    }
+}
+
+impl SortOrder {
+    // a leading "-" sorts in descending order, e.g. "-rss"
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (name, descending) = match value.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (value, false),
+        };
+        Ok(SortOrder {
+            column: parse_column(name)?,
+            descending,
+        })
+    }
+}
+
+pub fn parse_columns(value: &str) -> Result<Vec<Column>, String> {
+    value.split(',').map(|x| parse_column(x.trim())).collect()
+}
+
+fn parse_column(name: &str) -> Result<Column, String> {
+    Column::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Column::ALL.iter().map(|x| x.get_name()).collect();
+        format!(
+            r#"unknown column "{}", expected one of: {} or {}KEY"#,
+            name,
+            names.join(", "),
+            LABEL_COLUMN_PREFIX
+        )
+    })
+}
+
+pub fn get_process_rows(processes: Vec<ProcessInfo>) -> Vec<ProcessRow> {
+    processes
+        .into_iter()
+        .map(|process| ProcessRow {
+            stats: proc_stats::read_process_stats(&process.pid),
+            // only processes in a cgroup have accounting to show
+            cgroup_usage: process.cgroup.as_deref().and_then(cgroups::read_usage),
+            process,
+        })
+        .collect()
+}
+
+pub fn sort_rows(rows: &mut [ProcessRow], order: &SortOrder) {
+    rows.sort_by(|a, b| match order.descending {
+        true => order.column.compare(b, a),
+        false => order.column.compare(a, b),
+    });
+}
+
+fn get_pid_number(row: &ProcessRow) -> Option<u32> {
+    row.process.pid.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subcommands::DEFAULT_COLUMNS;
+    use crate::ProcessStatus;
+
+    #[test]
+    fn columns_should_be_parsed_from_comma_list() {
+        assert_eq!(
+            parse_columns("name, rss,cpu").unwrap(),
+            vec![Column::Name, Column::Rss, Column::Cpu]
+        );
+        assert_eq!(parse_columns(DEFAULT_COLUMNS).unwrap().len(), 9);
+
+        let err = parse_columns("name,memory").unwrap_err();
+        assert!(err.contains(r#""memory""#), "{}", err);
+    }
+
+    #[test]
+    fn label_columns_should_be_named_by_key() {
+        assert_eq!(
+            parse_columns("name,labels,label:env").unwrap(),
+            vec![
+                Column::Name,
+                Column::Labels,
+                Column::Label("env".to_string())
+            ]
+        );
+        assert!(parse_columns("label:").is_err());
+        assert!(parse_columns("label").is_err());
+
+        let mut row = get_test_row("web", None);
+        row.process
+            .labels
+            .insert("env".to_string(), "prod".to_string());
+        assert_eq!(Column::Label("env".to_string()).get_header(), "env");
+        assert_eq!(
+            Column::Label("env".to_string()).get_cell_string(&row),
+            "prod"
+        );
+        assert_eq!(Column::Label("team".to_string()).get_cell_string(&row), "-");
+        assert_eq!(Column::Labels.get_cell_string(&row), "env=prod");
+    }
+
+    #[test]
+    fn sort_order_should_be_descending_with_leading_dash() {
+        let order = SortOrder::parse("-rss").unwrap();
+        assert_eq!(order.column, Column::Rss);
+        assert!(order.descending);
+
+        assert!(!SortOrder::parse("pid").unwrap().descending);
+        assert!(SortOrder::parse("-").is_err());
+    }
+
+    #[test]
+    fn rows_should_sort_by_stats_with_unreadable_ones_first() {
+        let mut rows = vec![
+            get_test_row("big", Some(300)),
+            get_test_row("gone", None),
+            get_test_row("small", Some(100)),
+        ];
+
+        sort_rows(&mut rows, &SortOrder::parse("rss").unwrap());
+        let names: Vec<&str> = rows.iter().map(|x| x.process.name.as_str()).collect();
+        assert_eq!(names, ["gone", "small", "big"]);
+
+        sort_rows(&mut rows, &SortOrder::parse("-rss").unwrap());
+        assert_eq!(rows[0].process.name, "big");
+    }
+
+    #[test]
+    fn missing_stats_should_display_as_dash() {
+        let row = get_test_row("web", None);
+
+        assert_eq!(Column::Name.get_cell_string(&row), "web");
+        assert_eq!(Column::Status.get_cell_string(&row), "Running");
+        assert_eq!(Column::Rss.get_cell_string(&row), "-");
+        assert_eq!(Column::Cgroup.get_cell_string(&row), "-");
+    }
+
+    fn get_test_row(name: &str, rss_bytes: Option<u64>) -> ProcessRow {
+        ProcessRow {
+            process: ProcessInfo {
+                id: "aaaa".to_string(),
+                name: name.to_string(),
+                pid: "1".to_string(),
+                status: ProcessStatus::Running,
+                log_file: None,
+                spec: Default::default(),
+                after: vec![],
+                health: None,
+                restart_count: 0,
+                cgroup: None,
+                started_at: None,
+                labels: Default::default(),
+                exit_status: None,
+            },
+            stats: rss_bytes.map(|rss_bytes| ProcessStats {
+                uptime_secs: 10,
+                cpu_percent: 0.0,
+                cpu_time_secs: 0.0,
+                rss_bytes,
+                threads: 1,
+                open_fds: Some(3),
+            }),
+            cgroup_usage: None,
+        }
+    }
+}
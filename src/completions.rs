@@ -0,0 +1,135 @@
+use super::subcommands::PROCESS_IDENTIFIERS_SUBCOMMAND_NAME;
+use super::ProcessInfo;
+use clap::App;
+use clap_complete::Shell;
+
+const IDENTIFIER_ARG_NAME: &str = "process identifier";
+const IDENTIFIER_VALUE_NAME: &str = "PROCESS_IDENTIFIER";
+
+// clap's script, with tracked processes offered wherever a process identifier goes
+pub fn get_completions_script(shell_name: &str, app: &mut App) -> String {
+    let shell: Shell = shell_name.parse().expect("clap only accepts known shells");
+    let bin_name = app.get_name().to_string();
+    let subcommand_names = get_identifier_subcommand_names(app);
+    let mut script = vec![];
+    clap_complete::generate(shell, app, &bin_name, &mut script);
+    let script = String::from_utf8(script).expect("completion scripts should be UTF-8");
+
+    let identifiers_command = format!(
+        "{} {} 2>/dev/null",
+        bin_name, PROCESS_IDENTIFIERS_SUBCOMMAND_NAME
+    );
+    match shell {
+        Shell::Bash => script.replace(
+            &format!("<{}>", IDENTIFIER_VALUE_NAME),
+            &format!("$({})", identifiers_command),
+        ),
+        Shell::Zsh => get_zsh_script_with_identifiers(&script, &bin_name, &identifiers_command),
+        Shell::Fish => format!(
+            "{}complete -c {} -n \"__fish_seen_subcommand_from {}\" -f -a \"({})\"\n",
+            script,
+            bin_name,
+            subcommand_names.join(" "),
+            identifiers_command
+        ),
+        _ => unreachable!("only bash, zsh and fish are offered"),
+    }
+}
+
+fn get_identifier_subcommand_names(app: &App) -> Vec<String> {
+    app.get_subcommands()
+        .filter(|subcommand| {
+            subcommand
+                .get_arguments()
+                .any(|x| x.get_id() == IDENTIFIER_ARG_NAME)
+        })
+        .map(|x| x.get_name().to_string())
+        .collect()
+}
+
+// the identifier's positional spec is left without an action by clap, e.g.
+// ':process identifier -- Name, id or pid of process to inspect:' \
+fn get_zsh_script_with_identifiers(script: &str, bin_name: &str, command: &str) -> String {
+    let function_name = format!("_{}_processes", bin_name);
+    let function = format!(
+        "{}() {{\n    local -a processes\n    processes=(${{(f)\"$({})\"}})\n    compadd -a processes\n}}\n",
+        function_name, command
+    );
+    let spec_prefix = format!(":{} -- ", IDENTIFIER_ARG_NAME);
+
+    let lines: Vec<String> = script
+        .lines()
+        .map(|line| match line.strip_suffix(":' \\") {
+            Some(spec) if line.contains(&spec_prefix) => format!("{}:{}' \\", spec, function_name),
+            _ => line.to_string(),
+        })
+        .collect();
+    let script = lines.join("\n");
+    // defined ahead of the generated function, which runs right at the end of the file
+    script.replacen(
+        &format!("\n_{}() {{", bin_name),
+        &format!("\n{}\n_{}() {{", function, bin_name),
+        1,
+    ) + "\n"
+}
+
+// names first, since that's what is typed most, then ids
+pub fn get_process_identifiers(processes: &[ProcessInfo]) -> Vec<String> {
+    let mut identifiers: Vec<String> = vec![];
+    let names = processes.iter().map(|x| &x.name);
+    let ids = processes.iter().map(|x| &x.id).filter(|x| !x.is_empty());
+    for identifier in names.chain(ids) {
+        if !identifiers.contains(identifier) {
+            identifiers.push(identifier.clone());
+        }
+    }
+    identifiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifier_subcommands_should_be_found_by_their_positional() {
+        let names = get_identifier_subcommand_names(&crate::subcommands::get_app());
+
+        for name in ["stop", "restart", "signal", "inspect", "label", "list"] {
+            assert!(names.contains(&name.to_string()), "{}", name);
+        }
+        assert!(!names.contains(&"hatch".to_string()));
+    }
+
+    #[test]
+    fn every_script_should_complete_tracked_processes() {
+        let command = "eggsecutor __complete 2>/dev/null";
+
+        let bash = get_completions_script("bash", &mut crate::subcommands::get_app());
+        assert!(bash.contains(&format!("$({})", command)));
+        assert!(!bash.contains("<PROCESS_IDENTIFIER>"));
+
+        let zsh = get_completions_script("zsh", &mut crate::subcommands::get_app());
+        let identifier_specs = zsh.matches("_eggsecutor_processes' \\").count();
+        assert_eq!(identifier_specs, 6, "{}", zsh);
+        assert!(zsh.contains(&format!("processes=(${{(f)\"$({})\"}})", command)));
+        assert!(zsh.find("_eggsecutor_processes() {") < zsh.find("_eggsecutor() {"));
+
+        let fish = get_completions_script("fish", &mut crate::subcommands::get_app());
+        assert!(fish.contains(&format!("-f -a \"({})\"", command)));
+    }
+
+    #[test]
+    fn process_identifiers_should_list_names_before_ids_once() {
+        let processes: Vec<ProcessInfo> = serde_json::from_value(serde_json::json!([
+            {"id": "aaaa", "name": "web", "pid": "1", "status": "Running"},
+            {"id": "bbbb", "name": "web", "pid": "2", "status": "Running"},
+            {"name": "api", "pid": "3", "status": "Running"},
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            get_process_identifiers(&processes),
+            ["web", "api", "aaaa", "bbbb"]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use super::errors;
+use super::project;
+use super::xdg;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+const DEFAULT_SHELL: &str = "/bin/sh";
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub state_file: Option<String>,
+    // runs `hatch --shell` command lines, which it must accept through `-c`
+    pub shell: Option<String>,
+    // a delegated cgroup v2 directory to create the process cgroups in
+    pub cgroup_root: Option<String>,
+}
+
+impl Config {
+    pub fn get_shell(&self) -> &str {
+        self.shell.as_deref().unwrap_or(DEFAULT_SHELL)
+    }
+}
+
+pub fn load_config() -> Config {
+    let config_file_path = get_config_file_path();
+    if !Path::new(&config_file_path).exists() {
+        return Config::default();
+    }
+
+    let contents = fs::read_to_string(&config_file_path)
+        .unwrap_or_else(|err| errors::handle_invalid_config_error(&config_file_path, &err));
+    parse_config(&contents)
+        .unwrap_or_else(|err| errors::handle_invalid_config_error(&config_file_path, &err))
+}
+
+fn parse_config(contents: &str) -> Result<Config, toml::de::Error> {
+    toml::from_str(contents)
+}
+
+// a project directory brings its own config, which replaces the global one
+pub fn get_config_file_path() -> String {
+    match project::find_project_dir() {
+        Some(project_dir) => project::get_project_config_file_path(&project_dir)
+            .to_string_lossy()
+            .to_string(),
+        None => get_default_config_file_path_string(),
+    }
+}
+
+fn get_default_config_file_path_string() -> String {
+    xdg::get_config_dir()
+        .join("config.toml")
+        .to_string_lossy()
+        .to_string()
+}
+
+pub fn get_legacy_config_file_path_string() -> String {
+    "~/.eggsecutor.toml".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_config_should_parse_to_default() {
+        let config = parse_config("").expect("empty config should be valid");
+        assert!(config.state_file.is_none());
+    }
+
+    #[test]
+    fn config_should_parse_state_file_key() {
+        let contents = r#"state_file = "sqlite:~/.eggsecutor.db""#;
+        let config = parse_config(contents).expect("config should be valid");
+        assert_eq!(config.state_file.unwrap(), "sqlite:~/.eggsecutor.db");
+    }
+
+    #[test]
+    fn shell_should_default_to_sh() {
+        assert_eq!(Config::default().get_shell(), "/bin/sh");
+
+        let config = parse_config(r#"shell = "/bin/bash""#).expect("config should be valid");
+        assert_eq!(config.get_shell(), "/bin/bash");
+    }
+
+    #[test]
+    fn config_with_unknown_key_should_be_err() {
+        let contents = r#"not_a_key = true"#;
+        assert!(parse_config(contents).is_err());
+    }
+
+    #[test]
+    fn default_config_file_path_string_should_be_in_xdg_config_dir() {
+        let expected_default_path = xdg::get_config_dir().join("config.toml");
+        assert_eq!(
+            expected_default_path.to_string_lossy(),
+            get_default_config_file_path_string()
+        );
+    }
+
+    #[test]
+    fn legacy_config_file_path_string_should_be_set() {
+        let expected_legacy_path = "~/.eggsecutor.toml";
+        assert_eq!(expected_legacy_path, &get_legacy_config_file_path_string());
+    }
+}
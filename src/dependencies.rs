@@ -0,0 +1,179 @@
+use super::ProcessInfo;
+use std::collections::BTreeMap;
+
+// process name -> names of the processes it was hatched after
+pub type DependencyGraph = BTreeMap<String, Vec<String>>;
+
+pub fn get_dependency_graph(processes: &[ProcessInfo]) -> DependencyGraph {
+    let mut graph = DependencyGraph::new();
+    for process in processes {
+        add_dependencies(&mut graph, &process.name, &process.after);
+    }
+    graph
+}
+
+pub fn add_dependencies(graph: &mut DependencyGraph, name: &str, after: &[String]) {
+    let dependencies = graph.entry(name.to_string()).or_default();
+    for dependency in after {
+        if !dependencies.contains(dependency) {
+            dependencies.push(dependency.clone());
+        }
+    }
+}
+
+// returns the names along the first cycle found, starting and ending on the same name
+pub fn find_cycle(graph: &DependencyGraph) -> Option<Vec<String>> {
+    let mut finished: Vec<&str> = vec![];
+    for name in graph.keys() {
+        let mut path = vec![];
+        if let Some(cycle) = find_cycle_from(graph, name, &mut path, &mut finished) {
+            return Some(cycle);
+        }
+    }
+    None
+}
+
+// dependencies always come before the processes that were hatched after them
+pub fn get_start_order(graph: &DependencyGraph) -> Vec<String> {
+    let mut visited = vec![];
+    let mut order = vec![];
+    for name in graph.keys() {
+        visit_dependencies_first(graph, name, &mut visited, &mut order);
+    }
+    order
+}
+
+pub fn get_stop_order(graph: &DependencyGraph) -> Vec<String> {
+    let mut order = get_start_order(graph);
+    order.reverse();
+    order
+}
+
+// everything that directly or indirectly depends on `name`
+pub fn get_dependents(graph: &DependencyGraph, name: &str) -> Vec<String> {
+    let mut dependents: Vec<String> = vec![];
+    let mut pending = vec![name.to_string()];
+    while let Some(current) = pending.pop() {
+        for (dependent, after) in graph {
+            if after.contains(&current) && dependent != name && !dependents.contains(dependent) {
+                dependents.push(dependent.clone());
+                pending.push(dependent.clone());
+            }
+        }
+    }
+    dependents
+}
+
+fn find_cycle_from<'a>(
+    graph: &'a DependencyGraph,
+    name: &'a str,
+    path: &mut Vec<&'a str>,
+    finished: &mut Vec<&'a str>,
+) -> Option<Vec<String>> {
+    if let Some(start) = path.iter().position(|x| *x == name) {
+        let mut cycle: Vec<String> = path[start..].iter().map(|x| x.to_string()).collect();
+        cycle.push(name.to_string());
+        return Some(cycle);
+    }
+    if finished.contains(&name) {
+        return None;
+    }
+
+    path.push(name);
+    for dependency in graph.get(name).into_iter().flatten() {
+        if let Some(cycle) = find_cycle_from(graph, dependency, path, finished) {
+            return Some(cycle);
+        }
+    }
+    path.pop();
+    finished.push(name);
+    None
+}
+
+fn visit_dependencies_first<'a>(
+    graph: &'a DependencyGraph,
+    name: &'a str,
+    visited: &mut Vec<&'a str>,
+    order: &mut Vec<String>,
+) {
+    // marking before recursing means a cycle in an old state file can't recurse forever
+    if visited.contains(&name) {
+        return;
+    }
+    visited.push(name);
+    for dependency in graph.get(name).into_iter().flatten() {
+        visit_dependencies_first(graph, dependency, visited, order);
+    }
+    order.push(name.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph_without_dependencies_should_have_no_cycle() {
+        let graph = get_graph(&[("db", &[]), ("api", &["db"])]);
+        assert_eq!(find_cycle(&graph), None);
+    }
+
+    #[test]
+    fn cycle_should_be_found_with_its_path() {
+        let graph = get_graph(&[("db", &["api"]), ("api", &["proxy"]), ("proxy", &["db"])]);
+
+        let cycle = find_cycle(&graph).unwrap();
+
+        assert_eq!(cycle, vec!["api", "proxy", "db", "api"]);
+    }
+
+    #[test]
+    fn depending_on_itself_should_be_a_cycle() {
+        let graph = get_graph(&[("api", &["api"])]);
+        assert_eq!(find_cycle(&graph), Some(vec!["api".to_string(); 2]));
+    }
+
+    #[test]
+    fn start_order_should_put_dependencies_first() {
+        let graph = get_graph(&[
+            ("api", &["db", "proxy"]),
+            ("proxy", &["db"]),
+            ("worker", &[]),
+        ]);
+
+        let order = get_start_order(&graph);
+
+        assert_eq!(order, vec!["db", "proxy", "api", "worker"]);
+        assert_eq!(get_stop_order(&graph), vec!["worker", "api", "proxy", "db"]);
+    }
+
+    #[test]
+    fn start_order_should_terminate_on_cycles() {
+        let graph = get_graph(&[("a", &["b"]), ("b", &["a"])]);
+        assert_eq!(get_start_order(&graph).len(), 2);
+    }
+
+    #[test]
+    fn dependents_should_be_transitive() {
+        let graph = get_graph(&[
+            ("api", &["proxy"]),
+            ("proxy", &["db"]),
+            ("worker", &["api"]),
+            ("cron", &[]),
+        ]);
+
+        let mut dependents = get_dependents(&graph, "db");
+        dependents.sort();
+
+        assert_eq!(dependents, vec!["api", "proxy", "worker"]);
+        assert!(get_dependents(&graph, "cron").is_empty());
+    }
+
+    fn get_graph(edges: &[(&str, &[&str])]) -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        for (name, after) in edges {
+            let after: Vec<String> = after.iter().map(|x| x.to_string()).collect();
+            add_dependencies(&mut graph, name, &after);
+        }
+        graph
+    }
+}
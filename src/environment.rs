@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+// set in every child so it can tell which tracked process it is
+pub const NAME_ENV_KEY: &str = "EGGSECUTOR_NAME";
+pub const ID_ENV_KEY: &str = "EGGSECUTOR_ID";
+
+// whitespace around the key is dropped, the value is kept as given
+pub fn parse_env_assignment(assignment: &str) -> Result<(String, String), String> {
+    match assignment.split_once('=') {
+        Some((key, value)) if is_valid_env_key(key.trim()) => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!(
+            r#"invalid variable "{}", expected KEY=VALUE"#,
+            assignment
+        )),
+    }
+}
+
+pub fn load_env_file(path: &str) -> Result<BTreeMap<String, String>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    parse_env_file(&contents)
+}
+
+// the usual .env subset: comments, blank lines, an optional `export` and quoted values
+fn parse_env_file(contents: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut env = BTreeMap::new();
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = parse_env_assignment(line)
+            .map_err(|err| format!("line {}: {}", line_number + 1, err))?;
+        env.insert(key, unquote(value.trim()).to_string());
+    }
+    Ok(env)
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    value
+}
+
+fn is_valid_env_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|x: char| x.is_ascii_digit())
+        && key.chars().all(|x| x.is_ascii_alphanumeric() || x == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_assignment_should_split_on_first_equals() {
+        assert_eq!(
+            parse_env_assignment("DATABASE_URL=postgres://db?sslmode=off"),
+            Ok((
+                "DATABASE_URL".to_string(),
+                "postgres://db?sslmode=off".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_env_assignment("EMPTY="),
+            Ok(("EMPTY".to_string(), "".to_string()))
+        );
+    }
+
+    #[test]
+    fn env_assignment_key_should_be_trimmed() {
+        assert_eq!(
+            parse_env_assignment(" FOO =bar"),
+            Ok(("FOO".to_string(), "bar".to_string()))
+        );
+    }
+
+    #[test]
+    fn invalid_env_assignments_should_be_err() {
+        for assignment in ["NO_VALUE", "=value", "1KEY=value", "BAD-KEY=value"] {
+            assert!(
+                parse_env_assignment(assignment).is_err(),
+                "{} should be err",
+                assignment
+            );
+        }
+    }
+
+    #[test]
+    fn env_file_should_parse_common_dotenv_syntax() {
+        let contents = r#"
+            # database settings
+            export DATABASE_URL="postgres://localhost/app"
+            RUST_LOG='info'
+            PORT = 8000
+        "#;
+
+        let env = parse_env_file(contents).unwrap();
+
+        assert_eq!(env.len(), 3);
+        assert_eq!(env["DATABASE_URL"], "postgres://localhost/app");
+        assert_eq!(env["RUST_LOG"], "info");
+        assert_eq!(env["PORT"], "8000");
+    }
+
+    #[test]
+    fn invalid_env_file_line_should_report_line_number() {
+        let err = parse_env_file("GOOD=1\nnot a variable").unwrap_err();
+        assert!(err.starts_with("line 2"), "{}", err);
+    }
+}
@@ -1,20 +1,156 @@
+use super::output_display;
 use clap::{Error, ErrorKind};
+use std::fmt;
 use std::io;
+use std::process;
+
+// the same code clap exits with for these errors
+const ERROR_EXIT_CODE: i32 = 2;
 
 pub fn handle_spawn_failure(err_reason: io::Error) -> ! {
-    get_spawn_failure_error(err_reason).exit();
+    exit_with_error(get_spawn_failure_error(err_reason), "spawn_failed");
 }
 
 pub fn handle_no_file_data_error() -> ! {
-    get_no_file_data_error().exit();
+    exit_with_error(get_no_file_data_error(), "no_state_data");
 }
 
 pub fn handle_no_such_process_error(process_info: &str) -> ! {
-    get_no_such_process_error(process_info).exit();
+    exit_with_error(get_no_such_process_error(process_info), "no_such_process");
+}
+
+pub fn handle_untracked_process_error(process_info: &str) -> ! {
+    exit_with_error(get_untracked_process_error(process_info), "no_such_process");
+}
+
+pub fn handle_no_matching_processes_error() -> ! {
+    exit_with_error(get_no_matching_processes_error(), "no_such_process");
+}
+
+pub fn handle_confirmation_required_error(action: &str, count: usize) -> ! {
+    exit_with_error(
+        get_confirmation_required_error(action, count),
+        "confirmation_required",
+    );
 }
 
 pub fn handle_process_boot_error(err_reason: io::Error) -> ! {
-    get_process_boot_error(err_reason).exit();
+    exit_with_error(get_process_boot_error(err_reason), "process_exited");
+}
+
+pub fn handle_invalid_config_error<E: fmt::Display>(config_path: &str, err_reason: &E) -> ! {
+    exit_with_error(
+        get_invalid_config_error(config_path, err_reason),
+        "invalid_config",
+    );
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn handle_sqlite_unsupported_error() -> ! {
+    exit_with_error(get_sqlite_unsupported_error(), "sqlite_unsupported");
+}
+
+pub fn handle_invalid_namespace_error(namespace: &str) -> ! {
+    exit_with_error(get_invalid_namespace_error(namespace), "invalid_namespace");
+}
+
+pub fn handle_legacy_migration_error(err_reason: io::Error) -> ! {
+    exit_with_error(get_legacy_migration_error(err_reason), "migration_failed");
+}
+
+pub fn handle_export_file_error<E: fmt::Display>(err_reason: &E) -> ! {
+    exit_with_error(get_export_file_error(err_reason), "export_file_failed");
+}
+
+pub fn handle_import_name_conflict_error(name: &str) -> ! {
+    exit_with_error(get_import_name_conflict_error(name), "import_name_conflict");
+}
+
+pub fn handle_invalid_manifest_error(manifest_path: &str, err_reason: &str) -> ! {
+    exit_with_error(
+        get_invalid_manifest_error(manifest_path, err_reason),
+        "invalid_manifest",
+    );
+}
+
+pub fn handle_unknown_manifest_process_error(manifest_path: &str, name: &str) -> ! {
+    exit_with_error(
+        get_unknown_manifest_process_error(manifest_path, name),
+        "unknown_manifest_process",
+    );
+}
+
+pub fn handle_dependency_cycle_error(cycle: &[String]) -> ! {
+    exit_with_error(get_dependency_cycle_error(cycle), "dependency_cycle");
+}
+
+pub fn handle_dependency_timeout_error(name: &str) -> ! {
+    exit_with_error(get_dependency_timeout_error(name), "dependency_timeout");
+}
+
+pub fn handle_invalid_option_value_error(arg_name: &str, err_reason: &str) -> ! {
+    exit_with_error(
+        get_invalid_option_value_error(arg_name, err_reason),
+        "invalid_option_value",
+    );
+}
+
+pub fn handle_readiness_timeout_error(name: &str, pending_check: &str) -> ! {
+    exit_with_error(
+        get_readiness_timeout_error(name, pending_check),
+        "readiness_timeout",
+    );
+}
+
+pub fn handle_invalid_file_path_error() -> ! {
+    exit_with_error(get_invalid_file_path_error(), "invalid_file_path");
+}
+
+pub fn handle_unknown_signal_error(signal: &str) -> ! {
+    exit_with_error(get_unknown_signal_error(signal), "unknown_signal");
+}
+
+pub fn handle_unknown_man_page_error(subcommand_name: &str) -> ! {
+    exit_with_error(
+        get_unknown_man_page_error(subcommand_name),
+        "unknown_man_page",
+    );
+}
+
+pub fn handle_man_dir_error(dir: &str, err_reason: io::Error) -> ! {
+    exit_with_error(get_man_dir_error(dir, err_reason), "man_dir_unwritable");
+}
+
+pub fn handle_terminal_error(err_reason: io::Error) -> ! {
+    exit_with_error(get_terminal_error(err_reason), "terminal_unavailable");
+}
+
+// help and version requests come through here too, and are printed as usual
+pub fn handle_invalid_arguments_error(err: Error) -> ! {
+    match err.kind {
+        ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => err.exit(),
+        _ => exit_with_error(err, "invalid_arguments"),
+    }
+}
+
+pub fn handle_io_error(err_reason: io::Error) -> ! {
+    exit_with_error(get_unexpected_io_error(err_reason), "io_error");
+}
+
+// structured output gets a structured error, with a code scripts can match on
+// that stays the same when the message is reworded
+fn exit_with_error(err: Error, code: &str) -> ! {
+    if !output_display::is_structured_output() {
+        err.exit();
+    }
+
+    output_display::print_error_output(code, &get_error_message(&err));
+    process::exit(ERROR_EXIT_CODE);
+}
+
+fn get_error_message(err: &Error) -> String {
+    let message = err.to_string();
+    message.trim().trim_start_matches("error: ").to_string()
 }
 
 pub fn get_invalid_file_path_error() -> Error {
@@ -24,6 +160,27 @@ pub fn get_invalid_file_path_error() -> Error {
     )
 }
 
+fn get_unknown_signal_error(signal: &str) -> Error {
+    Error::with_description(
+        format!(
+            r#"unknown signal "{}", expected e.g. HUP or SIGUSR1"#,
+            signal
+        ),
+        ErrorKind::InvalidValue,
+    )
+}
+
+fn get_terminal_error(err_reason: io::Error) -> Error {
+    Error::with_description(
+        format!("an interactive terminal is needed: {}", err_reason),
+        ErrorKind::Io,
+    )
+}
+
+fn get_unexpected_io_error(err_reason: io::Error) -> Error {
+    Error::with_description(err_reason.to_string(), ErrorKind::Io)
+}
+
 fn get_spawn_failure_error(err_reason: io::Error) -> Error {
     Error::with_description(
         format!(
@@ -51,6 +208,30 @@ fn get_no_such_process_error(process_info: &str) -> Error {
     )
 }
 
+fn get_no_matching_processes_error() -> Error {
+    Error::with_description(
+        "no running process matches the given selectors".to_string(),
+        ErrorKind::InvalidValue,
+    )
+}
+
+fn get_confirmation_required_error(action: &str, count: usize) -> Error {
+    Error::with_description(
+        format!(
+            "{} processes match, pass --yes to {} all of them",
+            count, action
+        ),
+        ErrorKind::MissingRequiredArgument,
+    )
+}
+
+fn get_untracked_process_error(process_info: &str) -> Error {
+    Error::with_description(
+        format!(r#"no tracked process with identifier: "{}""#, process_info),
+        ErrorKind::InvalidValue,
+    )
+}
+
 fn get_process_boot_error(err_reason: io::Error) -> Error {
     Error::with_description(
         format!(
@@ -61,6 +242,142 @@ fn get_process_boot_error(err_reason: io::Error) -> Error {
     )
 }
 
+fn get_invalid_config_error<E: fmt::Display>(config_path: &str, err_reason: &E) -> Error {
+    Error::with_description(
+        format!(
+            r#"could not load config file "{}", details: {}"#,
+            config_path, err_reason
+        ),
+        ErrorKind::InvalidValue,
+    )
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn get_sqlite_unsupported_error() -> Error {
+    Error::with_description(
+        "sqlite state file requested, but eggsecutor was built without the \"sqlite\" feature"
+            .to_string(),
+        ErrorKind::InvalidValue,
+    )
+}
+
+fn get_invalid_namespace_error(namespace: &str) -> Error {
+    Error::with_description(
+        format!(
+            r#"invalid namespace "{}": only letters, digits, "-" and "_" are allowed"#,
+            namespace
+        ),
+        ErrorKind::InvalidValue,
+    )
+}
+
+fn get_legacy_migration_error(err_reason: io::Error) -> Error {
+    Error::with_description(
+        format!(
+            "could not move legacy state files into the XDG directories, details: {}",
+            err_reason
+        ),
+        ErrorKind::Io,
+    )
+}
+
+fn get_export_file_error<E: fmt::Display>(err_reason: &E) -> Error {
+    Error::with_description(
+        format!(
+            "could not read or write export file, details: {}",
+            err_reason
+        ),
+        ErrorKind::Io,
+    )
+}
+
+fn get_import_name_conflict_error(name: &str) -> Error {
+    Error::with_description(
+        format!(
+            r#"could not import processes: a process named "{}" is already running. Use --on-conflict to skip, rename or replace it"#,
+            name
+        ),
+        ErrorKind::InvalidValue,
+    )
+}
+
+fn get_invalid_manifest_error(manifest_path: &str, err_reason: &str) -> Error {
+    Error::with_description(
+        format!(
+            r#"could not load manifest "{}", details: {}"#,
+            manifest_path, err_reason
+        ),
+        ErrorKind::InvalidValue,
+    )
+}
+
+fn get_unknown_manifest_process_error(manifest_path: &str, name: &str) -> Error {
+    Error::with_description(
+        format!(
+            r#"no process named "{}" in manifest "{}""#,
+            name, manifest_path
+        ),
+        ErrorKind::InvalidValue,
+    )
+}
+
+fn get_dependency_cycle_error(cycle: &[String]) -> Error {
+    Error::with_description(
+        format!(
+            "could not hatch process: dependency cycle {}",
+            cycle.join(" -> ")
+        ),
+        ErrorKind::InvalidValue,
+    )
+}
+
+fn get_dependency_timeout_error(name: &str) -> Error {
+    Error::with_description(
+        format!(
+            r#"could not hatch process: gave up waiting for "{}" to be running"#,
+            name
+        ),
+        ErrorKind::Io,
+    )
+}
+
+fn get_invalid_option_value_error(arg_name: &str, err_reason: &str) -> Error {
+    Error::with_description(
+        format!("invalid value for --{}: {}", arg_name, err_reason),
+        ErrorKind::InvalidValue,
+    )
+}
+
+fn get_readiness_timeout_error(name: &str, pending_check: &str) -> Error {
+    Error::with_description(
+        format!(
+            r#"process "{}" was not ready in time and has been stopped, still waiting on: {}"#,
+            name, pending_check
+        ),
+        ErrorKind::Io,
+    )
+}
+
+fn get_unknown_man_page_error(subcommand_name: &str) -> Error {
+    Error::with_description(
+        format!(
+            r#"no man page for "{}", it should be one of the subcommands in `eggsecutor --help`"#,
+            subcommand_name
+        ),
+        ErrorKind::InvalidValue,
+    )
+}
+
+fn get_man_dir_error(dir: &str, err_reason: io::Error) -> Error {
+    Error::with_description(
+        format!(
+            r#"could not write man pages to "{}", details: {}"#,
+            dir, err_reason
+        ),
+        ErrorKind::Io,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,6 +403,50 @@ mod tests {
         check_err_matches_spec(process_err_msg, kind, clap_err_fn);
     }
 
+    #[test]
+    fn unknown_signal_error_should_return_invalid_value_clap_err() {
+        let clap_err_fn = || get_unknown_signal_error("SIGBOGUS");
+
+        check_err_matches_spec("SIGBOGUS", ErrorKind::InvalidValue, clap_err_fn);
+    }
+
+    #[test]
+    fn terminal_error_should_return_io_clap_err() {
+        let err_msg = "stdout is not a terminal";
+        let clap_err_fn = || get_terminal_error(get_io_error(err_msg));
+
+        check_err_matches_spec(err_msg, ErrorKind::Io, clap_err_fn);
+    }
+
+    #[test]
+    fn untracked_process_error_should_return_invalid_value_clap_err() {
+        let process_err_msg = "no tracked process with identifier";
+        let kind = clap::ErrorKind::InvalidValue;
+
+        let clap_err_fn = || get_untracked_process_error("web");
+        check_err_matches_spec(process_err_msg, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn no_matching_processes_error_should_return_invalid_value_clap_err() {
+        let clap_err_fn = || get_no_matching_processes_error();
+
+        check_err_matches_spec(
+            "no running process matches",
+            ErrorKind::InvalidValue,
+            clap_err_fn,
+        );
+    }
+
+    #[test]
+    fn confirmation_required_error_should_name_count_and_flag() {
+        let kind = ErrorKind::MissingRequiredArgument;
+        let clap_err_fn = || get_confirmation_required_error("stop", 3);
+
+        check_err_matches_spec("3 processes match", kind, clap_err_fn);
+        check_err_matches_spec("--yes to stop", kind, clap_err_fn);
+    }
+
     #[test]
     fn no_file_data_error_should_return_clap_io_err() {
         let process_err_msg = "no state file data found. Add a process to track first";
@@ -116,6 +477,174 @@ mod tests {
         check_err_matches_spec(process_err_msg, kind, clap_err_fn);
     }
 
+    #[test]
+    fn invalid_config_error_should_return_invalid_value_clap_error() {
+        let kind = ErrorKind::InvalidValue;
+        let config_path = "test-config-path";
+        let err_msg = "test config error";
+
+        let clap_err_fn = || get_invalid_config_error(config_path, &err_msg);
+
+        check_err_matches_spec(config_path, kind, clap_err_fn);
+        check_err_matches_spec(err_msg, kind, clap_err_fn);
+    }
+
+    #[test]
+    #[cfg(not(feature = "sqlite"))]
+    fn sqlite_unsupported_error_should_return_invalid_value_clap_error() {
+        let kind = ErrorKind::InvalidValue;
+        let process_err_msg = "built without the \"sqlite\" feature";
+
+        let clap_err_fn = || get_sqlite_unsupported_error();
+
+        check_err_matches_spec(process_err_msg, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn invalid_namespace_error_should_return_invalid_value_clap_error() {
+        let kind = ErrorKind::InvalidValue;
+        let namespace = "bad/namespace";
+
+        let clap_err_fn = || get_invalid_namespace_error(namespace);
+
+        check_err_matches_spec(namespace, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn legacy_migration_error_should_return_io_clap_err() {
+        let kind = ErrorKind::Io;
+        let err_msg = "test migration error";
+        let io_err = get_io_error(err_msg);
+
+        let clap_err_fn = || get_legacy_migration_error(io_err);
+
+        check_err_matches_spec(err_msg, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn export_file_error_should_return_io_clap_err() {
+        let kind = ErrorKind::Io;
+        let err_msg = "test export error";
+
+        let clap_err_fn = || get_export_file_error(&err_msg);
+
+        check_err_matches_spec(err_msg, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn import_name_conflict_error_should_return_invalid_value_clap_err() {
+        let kind = ErrorKind::InvalidValue;
+        let name = "test-conflicting-process";
+
+        let clap_err_fn = || get_import_name_conflict_error(name);
+
+        check_err_matches_spec(name, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn invalid_manifest_error_should_return_invalid_value_clap_err() {
+        let kind = ErrorKind::InvalidValue;
+        let manifest_path = "test-Eggfile.toml";
+        let err_msg = "test manifest error";
+
+        let clap_err_fn = || get_invalid_manifest_error(manifest_path, err_msg);
+
+        check_err_matches_spec(manifest_path, kind, clap_err_fn);
+        check_err_matches_spec(err_msg, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn unknown_manifest_process_error_should_return_invalid_value_clap_err() {
+        let kind = ErrorKind::InvalidValue;
+        let name = "test-missing-process";
+
+        let clap_err_fn = || get_unknown_manifest_process_error("Eggfile.toml", name);
+
+        check_err_matches_spec(name, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn dependency_cycle_error_should_return_invalid_value_clap_err() {
+        let kind = ErrorKind::InvalidValue;
+        let cycle = vec!["api".to_string(), "db".to_string(), "api".to_string()];
+
+        let clap_err_fn = || get_dependency_cycle_error(&cycle);
+
+        check_err_matches_spec("api -> db -> api", kind, clap_err_fn);
+    }
+
+    #[test]
+    fn dependency_timeout_error_should_return_io_clap_err() {
+        let kind = ErrorKind::Io;
+        let name = "test-dependency";
+
+        let clap_err_fn = || get_dependency_timeout_error(name);
+
+        check_err_matches_spec(name, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn invalid_option_value_error_should_return_invalid_value_clap_err() {
+        let kind = ErrorKind::InvalidValue;
+        let err_msg = "test readiness error";
+
+        let clap_err_fn = || get_invalid_option_value_error("ready-tcp", err_msg);
+
+        check_err_matches_spec("--ready-tcp", kind, clap_err_fn);
+        check_err_matches_spec(err_msg, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn readiness_timeout_error_should_return_io_clap_err() {
+        let kind = ErrorKind::Io;
+        let pending_check = "TCP port 8000 accepting connections";
+
+        let clap_err_fn = || get_readiness_timeout_error("test-process", pending_check);
+
+        check_err_matches_spec("test-process", kind, clap_err_fn);
+        check_err_matches_spec(pending_check, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn unknown_man_page_error_should_return_invalid_value_clap_err() {
+        let kind = clap::ErrorKind::InvalidValue;
+
+        let clap_err_fn = || get_unknown_man_page_error("stpo");
+
+        check_err_matches_spec(r#""stpo""#, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn man_dir_error_should_return_io_clap_err() {
+        let kind = clap::ErrorKind::Io;
+        let err_msg = "test man dir error";
+        let io_err = get_io_error(err_msg);
+
+        let clap_err_fn = || get_man_dir_error("/usr/share/man/man1", io_err);
+
+        check_err_matches_spec(err_msg, kind, clap_err_fn);
+    }
+
+    #[test]
+    fn error_message_should_not_have_prefix() {
+        let err = get_no_such_process_error("web");
+        assert_eq!(
+            get_error_message(&err),
+            r#"couldn not stop process. no matching process with identifier: "web""#
+        );
+    }
+
+    #[test]
+    fn unexpected_io_error_should_return_clap_io_error() {
+        let kind = ErrorKind::Io;
+        let io_err_msg = "test io error";
+        let io_err = get_io_error(io_err_msg);
+
+        let clap_err_fn = || get_unexpected_io_error(io_err);
+
+        check_err_matches_spec(io_err_msg, kind, clap_err_fn);
+    }
+
     fn check_err_matches_spec<F>(err_msg: &str, error_kind: ErrorKind, err_factory: F)
     where
         F: FnOnce() -> clap::Error,
@@ -127,6 +656,6 @@ mod tests {
     }
 
     fn get_io_error(err_msg: &str) -> io::Error {
-        io::Error::new(io::ErrorKind::Other, err_msg)
+        io::Error::other(err_msg)
     }
 }
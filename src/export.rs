@@ -0,0 +1,387 @@
+use super::labels::Labels;
+use super::{LaunchSpec, ProcessInfo};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const EXPORT_FILE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    Toml,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "toml" => ExportFormat::Toml,
+            _ => ExportFormat::Json,
+        }
+    }
+
+    pub fn from_file_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|x| x.to_str()) {
+            Some("toml") => ExportFormat::Toml,
+            _ => ExportFormat::Json,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictStrategy {
+    Fail,
+    Skip,
+    Rename,
+    Replace,
+}
+
+impl ConflictStrategy {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "skip" => ConflictStrategy::Skip,
+            "rename" => ConflictStrategy::Rename,
+            "replace" => ConflictStrategy::Replace,
+            _ => ConflictStrategy::Fail,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ExportedProcess {
+    pub name: String,
+    pub spec: LaunchSpec,
+    #[serde(default)]
+    pub after: Vec<String>,
+    #[serde(default)]
+    pub labels: Labels,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ImportAction {
+    Hatch {
+        name: String,
+        spec: LaunchSpec,
+        after: Vec<String>,
+        labels: Labels,
+    },
+    Replace {
+        name: String,
+        spec: LaunchSpec,
+        after: Vec<String>,
+        labels: Labels,
+    },
+    Skip {
+        name: String,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportFile {
+    version: u32,
+    #[serde(default)]
+    processes: Vec<ExportedProcess>,
+}
+
+// records from before launch specs were tracked can't be started again,
+// so their names are handed back to be reported instead
+pub fn get_exported_processes(processes: Vec<ProcessInfo>) -> (Vec<ExportedProcess>, Vec<String>) {
+    let mut exported = vec![];
+    let mut skipped = vec![];
+    for process in processes {
+        match process.spec.path.is_empty() {
+            true => skipped.push(process.name),
+            false => exported.push(ExportedProcess {
+                name: process.name,
+                spec: process.spec,
+                after: process.after,
+                labels: process.labels,
+            }),
+        }
+    }
+    (exported, skipped)
+}
+
+pub fn serialize_exported_processes(
+    processes: Vec<ExportedProcess>,
+    format: ExportFormat,
+) -> Result<String, String> {
+    let export_file = ExportFile {
+        version: EXPORT_FILE_VERSION,
+        processes,
+    };
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&export_file)
+            .map(|x| x + "\n")
+            .map_err(|err| err.to_string()),
+        // going through a toml::Value puts plain values ahead of nested tables,
+        // which TOML requires but our field order doesn't guarantee
+        ExportFormat::Toml => toml::Value::try_from(&export_file)
+            .and_then(|x| toml::to_string(&x))
+            .map_err(|err| err.to_string()),
+    }
+}
+
+pub fn parse_exported_processes(
+    contents: &str,
+    format: ExportFormat,
+) -> Result<Vec<ExportedProcess>, String> {
+    let export_file: ExportFile = match format {
+        ExportFormat::Json => serde_json::from_str(contents).map_err(|err| err.to_string())?,
+        ExportFormat::Toml => toml::from_str(contents).map_err(|err| err.to_string())?,
+    };
+
+    match export_file.version {
+        EXPORT_FILE_VERSION => Ok(export_file.processes),
+        version => Err(format!("unsupported export file version {}", version)),
+    }
+}
+
+// bare command names are looked up in $PATH when spawned, as in manifests,
+// so only paths that name a file can be checked up front
+pub fn is_spec_path_valid(spec: &LaunchSpec) -> bool {
+    !spec.path.contains('/') || Path::new(&spec.path).exists()
+}
+
+// on `Fail`, the name of the first conflicting process is returned as the error
+pub fn plan_import(
+    processes: Vec<ExportedProcess>,
+    running_names: &[String],
+    on_conflict: ConflictStrategy,
+) -> Result<Vec<ImportAction>, String> {
+    let mut taken_names: Vec<String> = running_names.to_vec();
+    let mut actions = vec![];
+
+    for process in processes {
+        let ExportedProcess {
+            name,
+            spec,
+            after,
+            labels,
+        } = process;
+        if !taken_names.contains(&name) {
+            taken_names.push(name.clone());
+            actions.push(ImportAction::Hatch {
+                name,
+                spec,
+                after,
+                labels,
+            });
+            continue;
+        }
+
+        let action = match on_conflict {
+            ConflictStrategy::Fail => return Err(name),
+            ConflictStrategy::Skip => ImportAction::Skip { name },
+            ConflictStrategy::Replace => ImportAction::Replace {
+                name,
+                spec,
+                after,
+                labels,
+            },
+            ConflictStrategy::Rename => {
+                let name = get_unused_name(&name, &taken_names);
+                taken_names.push(name.clone());
+                ImportAction::Hatch {
+                    name,
+                    spec,
+                    after,
+                    labels,
+                }
+            }
+        };
+        actions.push(action);
+    }
+    Ok(actions)
+}
+
+fn get_unused_name(name: &str, taken_names: &[String]) -> String {
+    (2..)
+        .map(|suffix| format!("{}-{}", name, suffix))
+        .find(|candidate| !taken_names.contains(candidate))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_format_should_be_inferred_from_extension() {
+        assert_eq!(
+            ExportFormat::from_file_path("backup.toml"),
+            ExportFormat::Toml
+        );
+        assert_eq!(
+            ExportFormat::from_file_path("backup.json"),
+            ExportFormat::Json
+        );
+        assert_eq!(ExportFormat::from_file_path("backup"), ExportFormat::Json);
+    }
+
+    #[test]
+    fn processes_without_launch_spec_should_be_skipped_on_export() {
+        let processes: Vec<ProcessInfo> = serde_json::from_str(
+            r#"[
+                {"name":"OLD","pid":"1","status":"Running"},
+                {"name":"NEW","pid":"2","status":"Running","spec":{"path":"/bin/new","args":["-v"]},
+                 "after":["db"],"labels":{"env":"prod"}}
+            ]"#,
+        )
+        .unwrap();
+
+        let (exported, skipped) = get_exported_processes(processes);
+
+        assert_eq!(skipped, vec!["OLD"]);
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].name, "NEW");
+        assert_eq!(exported[0].spec.args, vec!["-v"]);
+        assert_eq!(exported[0].after, vec!["db"]);
+        assert_eq!(exported[0].labels["env"], "prod");
+    }
+
+    #[test]
+    fn exported_processes_should_round_trip_in_every_format() {
+        for format in [ExportFormat::Json, ExportFormat::Toml] {
+            let contents = serialize_exported_processes(vec![get_exported_process("web")], format)
+                .expect("processes should serialize");
+
+            // pids are machine specific and must never be exported
+            assert!(!contents.contains("pid"));
+
+            let parsed = parse_exported_processes(&contents, format)
+                .expect("serialized processes should parse");
+            assert_eq!(parsed, vec![get_exported_process("web")]);
+        }
+    }
+
+    #[test]
+    fn nested_launch_spec_fields_should_round_trip_through_toml() {
+        let spec: LaunchSpec = serde_json::from_value(serde_json::json!({
+            "path": "/usr/local/bin/web",
+            "env": {"PORT": "8000"},
+            "log_file": "/var/log/web.log",
+            "health": {
+                "probe": {"type": "http", "port": 8000, "path": "/health"},
+                "interval_secs": 30,
+                "failure_threshold": 3,
+            },
+            "limits": [{"resource": "nofile", "soft": 1024, "hard": 4096}],
+        }))
+        .unwrap();
+        let process = ExportedProcess {
+            name: "web".to_string(),
+            spec: spec.clone(),
+            after: vec![],
+            labels: Labels::new(),
+        };
+
+        let contents = serialize_exported_processes(vec![process], ExportFormat::Toml)
+            .expect("processes should serialize");
+        let parsed = parse_exported_processes(&contents, ExportFormat::Toml)
+            .expect("serialized processes should parse");
+
+        assert_eq!(parsed[0].spec, spec);
+    }
+
+    #[test]
+    fn bare_command_spec_should_be_valid_after_round_trip() {
+        let process = ExportedProcess {
+            name: "web".to_string(),
+            spec: LaunchSpec {
+                path: "python3".to_string(),
+                args: vec!["-m".to_string(), "http.server".to_string()],
+                ..LaunchSpec::default()
+            },
+            after: vec![],
+            labels: Labels::new(),
+        };
+
+        let contents = serialize_exported_processes(vec![process], ExportFormat::Json)
+            .expect("processes should serialize");
+        let parsed = parse_exported_processes(&contents, ExportFormat::Json)
+            .expect("serialized processes should parse");
+
+        assert_eq!(parsed[0].spec.path, "python3");
+        assert!(is_spec_path_valid(&parsed[0].spec));
+    }
+
+    #[test]
+    fn missing_file_path_in_spec_should_be_invalid() {
+        let spec = LaunchSpec {
+            path: "/nonexistent/bin/web".to_string(),
+            ..LaunchSpec::default()
+        };
+        assert!(!is_spec_path_valid(&spec));
+    }
+
+    #[test]
+    fn unknown_export_file_version_should_be_err() {
+        let contents = r#"{"version": 99, "processes": []}"#;
+        assert!(parse_exported_processes(contents, ExportFormat::Json).is_err());
+    }
+
+    #[test]
+    fn import_without_conflicts_should_hatch_everything() {
+        let actions = plan_import(
+            vec![get_exported_process("web")],
+            &[],
+            ConflictStrategy::Fail,
+        )
+        .unwrap();
+
+        assert_eq!(
+            actions,
+            vec![ImportAction::Hatch {
+                name: "web".to_string(),
+                spec: get_exported_process("web").spec,
+                after: vec!["db".to_string()],
+                labels: get_exported_process("web").labels,
+            }]
+        );
+    }
+
+    #[test]
+    fn import_conflict_should_follow_strategy() {
+        let running_names = vec!["web".to_string(), "web-2".to_string()];
+        let plan =
+            |strategy| plan_import(vec![get_exported_process("web")], &running_names, strategy);
+
+        assert_eq!(plan(ConflictStrategy::Fail), Err("web".to_string()));
+        assert_eq!(
+            plan(ConflictStrategy::Skip).unwrap(),
+            vec![ImportAction::Skip {
+                name: "web".to_string()
+            }]
+        );
+        assert!(matches!(
+            &plan(ConflictStrategy::Replace).unwrap()[0],
+            ImportAction::Replace { name, .. } if name == "web"
+        ));
+        assert!(matches!(
+            &plan(ConflictStrategy::Rename).unwrap()[0],
+            ImportAction::Hatch { name, .. } if name == "web-3"
+        ));
+    }
+
+    #[test]
+    fn duplicate_names_within_import_should_conflict_with_each_other() {
+        let processes = vec![get_exported_process("web"), get_exported_process("web")];
+
+        let actions = plan_import(processes, &[], ConflictStrategy::Rename).unwrap();
+
+        assert!(matches!(&actions[1], ImportAction::Hatch { name, .. } if name == "web-2"));
+    }
+
+    fn get_exported_process(name: &str) -> ExportedProcess {
+        ExportedProcess {
+            name: name.to_string(),
+            spec: LaunchSpec {
+                path: format!("/usr/local/bin/{}", name),
+                args: vec!["--port".to_string(), "8000".to_string()],
+                ..LaunchSpec::default()
+            },
+            after: vec!["db".to_string()],
+            labels: Labels::from([("env".to_string(), "prod".to_string())]),
+        }
+    }
+}
@@ -1,21 +1,141 @@
+use super::config;
 use super::errors;
+use super::project;
+#[cfg(feature = "sqlite")]
+use super::sqlite_state;
+use super::xdg;
 use super::ProcessInfo;
 use std::env;
-use std::fs::{self, File};
+use std::fs::{self, File, OpenOptions};
 use std::io;
-use std::path::Path;
+use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
 
 // TODO: this is not a good cross dependency; find fix.
 use super::is_process_alive;
 
 type Processes = Vec<ProcessInfo>;
 
+const SQLITE_STATE_PREFIX: &str = "sqlite:";
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+// files sqlite keeps next to the database while a transaction is open
+const SQLITE_SIDECAR_SUFFIXES: [&str; 3] = ["-journal", "-wal", "-shm"];
+
+#[derive(Debug, PartialEq)]
+enum StateLocation {
+    Json(String),
+    Sqlite(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StateSource {
+    EnvVar,
+    ConfigFile(String),
+    ProjectDir(String),
+    Default,
+}
+
+pub enum ProcessLookup<'a> {
+    Name(&'a str),
+    Id(&'a str),
+    Pid(&'a str),
+}
+
+impl<'a> ProcessLookup<'a> {
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        match self {
+            ProcessLookup::Name(name) => process.name == *name,
+            ProcessLookup::Id(id) => process.id == *id,
+            ProcessLookup::Pid(pid) => process.pid == *pid,
+        }
+    }
+}
+
 pub fn write_processes_to_state_file(processes: Processes) -> io::Result<()> {
-    let state_file_path = get_state_file_path();
-    let updated_processes = serde_json::to_string(&processes)?;
-    fs::write(state_file_path, updated_processes.as_bytes())?;
+    match get_state_location() {
+        StateLocation::Json(path) => {
+            let _lock = lock_state()?;
+            write_processes_to_json_file(&path, &processes)
+        }
+        #[cfg(feature = "sqlite")]
+        StateLocation::Sqlite(path) => {
+            sqlite_state::replace_all_processes(&path, &get_json_state_file_path(), &processes)
+        }
+        #[cfg(not(feature = "sqlite"))]
+        StateLocation::Sqlite(_) => errors::handle_sqlite_unsupported_error(),
+    }
+}
 
-    Ok(())
+pub fn add_process_to_state_file(process: ProcessInfo) -> io::Result<()> {
+    match get_state_location() {
+        StateLocation::Json(path) => {
+            let _lock = lock_state()?;
+            let mut processes = read_processes_from_json_file(&path)?;
+            processes.push(process);
+            write_processes_to_json_file(&path, &processes)
+        }
+        #[cfg(feature = "sqlite")]
+        StateLocation::Sqlite(path) => {
+            sqlite_state::insert_process(&path, &get_json_state_file_path(), &process)
+        }
+        #[cfg(not(feature = "sqlite"))]
+        StateLocation::Sqlite(_) => errors::handle_sqlite_unsupported_error(),
+    }
+}
+
+pub fn remove_process_from_state_file(pid: &str) -> io::Result<()> {
+    match get_state_location() {
+        StateLocation::Json(path) => {
+            let _lock = lock_state()?;
+            let mut processes = read_processes_from_json_file(&path)?;
+            processes.retain(|x| x.pid != pid);
+            write_processes_to_json_file(&path, &processes)
+        }
+        #[cfg(feature = "sqlite")]
+        StateLocation::Sqlite(path) => {
+            sqlite_state::delete_process_by_pid(&path, &get_json_state_file_path(), pid)
+        }
+        #[cfg(not(feature = "sqlite"))]
+        StateLocation::Sqlite(_) => errors::handle_sqlite_unsupported_error(),
+    }
+}
+
+// the record with the same pid is replaced, nothing is added if there isn't one
+pub fn update_process_in_state_file(process: ProcessInfo) -> io::Result<()> {
+    match get_state_location() {
+        StateLocation::Json(path) => {
+            let _lock = lock_state()?;
+            let mut processes = read_processes_from_json_file(&path)?;
+            for existing in processes.iter_mut().filter(|x| x.pid == process.pid) {
+                *existing = process.clone();
+            }
+            write_processes_to_json_file(&path, &processes)
+        }
+        #[cfg(feature = "sqlite")]
+        StateLocation::Sqlite(path) => {
+            sqlite_state::update_process_by_pid(&path, &get_json_state_file_path(), &process)
+        }
+        #[cfg(not(feature = "sqlite"))]
+        StateLocation::Sqlite(_) => errors::handle_sqlite_unsupported_error(),
+    }
+}
+
+pub fn find_processes_in_state_file(lookup: ProcessLookup) -> io::Result<Processes> {
+    match get_state_location() {
+        StateLocation::Json(path) => {
+            let mut processes = read_processes_from_json_file(&path)?;
+            processes.retain(|process| lookup.matches(process));
+            Ok(processes)
+        }
+        #[cfg(feature = "sqlite")]
+        StateLocation::Sqlite(path) => {
+            sqlite_state::find_processes(&path, &get_json_state_file_path(), &lookup)
+        }
+        #[cfg(not(feature = "sqlite"))]
+        StateLocation::Sqlite(_) => errors::handle_sqlite_unsupported_error(),
+    }
 }
 
 pub fn get_running_processes_from_state_file() -> io::Result<Processes> {
@@ -24,6 +144,92 @@ pub fn get_running_processes_from_state_file() -> io::Result<Processes> {
     Ok(processes)
 }
 
+pub fn get_running_processes_from_namespace(namespace: &str) -> io::Result<Processes> {
+    let location = parse_state_location(&get_namespaced_state_file_path(
+        &get_base_state_file_path(),
+        namespace,
+    ));
+    let mut processes = get_all_processes_from_location(location, namespace)?;
+    processes.retain(|process| is_process_alive(&process.pid).unwrap());
+    Ok(processes)
+}
+
+pub fn get_all_namespaces() -> io::Result<Vec<String>> {
+    let base_path = match parse_state_location(&get_base_state_file_path()) {
+        StateLocation::Json(path) => path,
+        StateLocation::Sqlite(path) => path,
+    };
+    let base_path = Path::new(&base_path);
+    let base_file_name = base_path
+        .file_name()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let state_dir = match base_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => Path::new(".").to_path_buf(),
+    };
+
+    let mut namespaces = vec![];
+    if state_dir.exists() {
+        for entry in fs::read_dir(state_dir)? {
+            let file_name = entry?.file_name().to_string_lossy().to_string();
+            if let Some(namespace) = get_namespace_from_file_name(&base_file_name, &file_name) {
+                namespaces.push(namespace);
+            }
+        }
+    }
+
+    // the default namespace always comes first, the rest alphabetically
+    namespaces.sort_by_key(|x| (x != DEFAULT_NAMESPACE, x.clone()));
+    Ok(namespaces)
+}
+
+pub fn get_current_namespace() -> String {
+    let namespace = env::var(get_namespace_env_key()).unwrap_or_default();
+    if namespace.is_empty() {
+        return DEFAULT_NAMESPACE.to_string();
+    }
+
+    if !is_valid_namespace(&namespace) {
+        errors::handle_invalid_namespace_error(&namespace);
+    }
+    namespace
+}
+
+pub fn set_current_namespace(namespace: &str) {
+    env::set_var(get_namespace_env_key(), namespace);
+}
+
+pub fn get_state_file_path_and_source() -> (String, StateSource) {
+    let (base_path, source) = resolve_base_state_file_path();
+    let path = get_namespaced_state_file_path(&base_path, &get_current_namespace());
+    (path, source)
+}
+
+pub fn get_logs_dir() -> PathBuf {
+    match project::find_project_dir() {
+        Some(project_dir) => project::get_project_logs_dir(&project_dir),
+        None => xdg::get_state_dir().join("logs"),
+    }
+}
+
+pub fn open_log_file_for_process(
+    name: &str,
+    requested_log_file: Option<&str>,
+) -> io::Result<(PathBuf, File)> {
+    let log_file_path = match requested_log_file {
+        Some(log_file) => PathBuf::from(log_file),
+        None => get_logs_dir().join(get_log_file_name(name)),
+    };
+    create_parent_dir(&log_file_path.to_string_lossy())?;
+
+    let log_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_file_path)?;
+    Ok((log_file_path, log_file))
+}
+
 pub fn check_if_file_is_valid(filename: &str) -> Result<(), clap::Error> {
     match Path::new(filename).exists() {
         true => Ok(()),
@@ -32,34 +238,237 @@ pub fn check_if_file_is_valid(filename: &str) -> Result<(), clap::Error> {
 }
 
 pub fn create_state_file_if_not_exists() -> io::Result<()> {
-    let state_file_path = get_state_file_path();
-    if !Path::new(&state_file_path).exists() {
-        File::create(&state_file_path)?;
+    match get_state_location() {
+        StateLocation::Json(path) => {
+            if !Path::new(&path).exists() {
+                create_parent_dir(&path)?;
+                File::create(&path)?;
+            }
+            Ok(())
+        }
+        #[cfg(feature = "sqlite")]
+        StateLocation::Sqlite(path) => {
+            sqlite_state::create_database_if_not_exists(&path, &get_json_state_file_path())
+        }
+        #[cfg(not(feature = "sqlite"))]
+        StateLocation::Sqlite(_) => errors::handle_sqlite_unsupported_error(),
     }
-    Ok(())
 }
 
-fn get_all_processes_from_state_file() -> io::Result<Processes> {
-    let state_file_path = get_state_file_path();
-    let contents = fs::read_to_string(state_file_path)?;
+pub fn read_processes_from_json_file(path: &str) -> io::Result<Processes> {
+    let contents = fs::read_to_string(path)?;
+
+    // a freshly created state file is empty rather than an empty list
+    if contents.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
     let processes: Vec<ProcessInfo> = serde_json::from_str(&contents)?;
     Ok(processes)
 }
 
+// the JSON state a new sqlite database takes over: same project, same namespace
+#[cfg(feature = "sqlite")]
+fn get_json_state_file_path() -> String {
+    get_namespaced_json_state_file_path(&get_current_namespace())
+}
+
+#[cfg(feature = "sqlite")]
+fn get_namespaced_json_state_file_path(namespace: &str) -> String {
+    let base_path = match project::find_project_dir() {
+        Some(project_dir) => project::get_project_state_file_path(&project_dir)
+            .to_string_lossy()
+            .to_string(),
+        None => shellexpand::tilde(&get_default_state_file_path_string()).to_string(),
+    };
+    get_namespaced_state_file_path(&base_path, namespace)
+}
+
+fn write_processes_to_json_file(path: &str, processes: &[ProcessInfo]) -> io::Result<()> {
+    create_parent_dir(path)?;
+    let updated_processes = serde_json::to_string(processes)?;
+    fs::write(path, updated_processes.as_bytes())?;
+
+    Ok(())
+}
+
+fn create_parent_dir(path: &str) -> io::Result<()> {
+    match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+// serializes read-modify-write cycles on the JSON state between invocations;
+// the lock is released when the returned file is dropped
+fn lock_state() -> io::Result<File> {
+    let runtime_dir = xdg::get_runtime_dir();
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&runtime_dir)?;
+
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(runtime_dir.join("state.lock"))?;
+
+    // SAFETY: the fd is owned by lock_file, which outlives the call
+    if unsafe { libc::flock(lock_file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(lock_file)
+}
+
+// including the ones that are no longer alive
+pub fn get_all_processes_from_state_file() -> io::Result<Processes> {
+    get_all_processes_from_location(get_state_location(), &get_current_namespace())
+}
+
+#[cfg_attr(not(feature = "sqlite"), allow(unused_variables))]
+fn get_all_processes_from_location(
+    location: StateLocation,
+    namespace: &str,
+) -> io::Result<Processes> {
+    match location {
+        StateLocation::Json(path) => read_processes_from_json_file(&path),
+        #[cfg(feature = "sqlite")]
+        StateLocation::Sqlite(path) => {
+            sqlite_state::get_all_processes(&path, &get_namespaced_json_state_file_path(namespace))
+        }
+        #[cfg(not(feature = "sqlite"))]
+        StateLocation::Sqlite(_) => errors::handle_sqlite_unsupported_error(),
+    }
+}
+
+fn get_state_location() -> StateLocation {
+    parse_state_location(&get_state_file_path())
+}
+
+fn parse_state_location(path_string: &str) -> StateLocation {
+    match path_string.strip_prefix(SQLITE_STATE_PREFIX) {
+        Some(path) => StateLocation::Sqlite(path.to_string()),
+        None => StateLocation::Json(path_string.to_string()),
+    }
+}
+
 fn get_state_file_path() -> String {
-    let path_string = match env::var(get_state_file_env_key()) {
-        Ok(state_path) => state_path,
-        Err(_) => get_default_state_file_path_string(),
+    get_namespaced_state_file_path(&get_base_state_file_path(), &get_current_namespace())
+}
+
+fn get_namespaced_state_file_path(base_path: &str, namespace: &str) -> String {
+    match namespace == DEFAULT_NAMESPACE {
+        true => base_path.to_string(),
+        false => format!("{}.{}", base_path, namespace),
+    }
+}
+
+pub fn get_namespace_from_file_name(base_file_name: &str, file_name: &str) -> Option<String> {
+    if file_name == base_file_name {
+        return Some(DEFAULT_NAMESPACE.to_string());
+    }
+
+    let namespace = file_name.strip_prefix(base_file_name)?.strip_prefix('.')?;
+    let is_sqlite_sidecar = SQLITE_SIDECAR_SUFFIXES
+        .iter()
+        .any(|suffix| namespace.ends_with(suffix));
+
+    match is_valid_namespace(namespace) && !is_sqlite_sidecar {
+        true => Some(namespace.to_string()),
+        false => None,
+    }
+}
+
+fn is_valid_namespace(namespace: &str) -> bool {
+    !namespace.is_empty()
+        && namespace
+            .chars()
+            .all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_')
+}
+
+fn get_base_state_file_path() -> String {
+    resolve_base_state_file_path().0
+}
+
+fn resolve_base_state_file_path() -> (String, StateSource) {
+    if let Ok(state_path) = env::var(get_state_file_env_key()) {
+        return (expand_state_file_path(&state_path), StateSource::EnvVar);
+    }
+
+    resolve_base_state_file_path_from(
+        config::load_config().state_file,
+        project::find_project_dir(),
+    )
+}
+
+fn resolve_base_state_file_path_from(
+    config_state_path: Option<String>,
+    project_dir: Option<PathBuf>,
+) -> (String, StateSource) {
+    if let Some(state_path) = config_state_path {
+        let state_path = match &project_dir {
+            Some(project_dir) => resolve_project_relative_path(project_dir, &state_path),
+            None => state_path,
+        };
+        let source = StateSource::ConfigFile(config::get_config_file_path());
+        return (expand_state_file_path(&state_path), source);
+    }
+
+    match project_dir {
+        Some(project_dir) => {
+            let state_path = project::get_project_state_file_path(&project_dir);
+            let source = StateSource::ProjectDir(project_dir.to_string_lossy().to_string());
+            (state_path.to_string_lossy().to_string(), source)
+        }
+        None => (
+            expand_state_file_path(&get_default_state_file_path_string()),
+            StateSource::Default,
+        ),
+    }
+}
+
+// relative paths in a project config are relative to the project dir, not the cwd
+fn resolve_project_relative_path(project_dir: &Path, path_string: &str) -> String {
+    let (prefix, path) = match path_string.strip_prefix(SQLITE_STATE_PREFIX) {
+        Some(path) => (SQLITE_STATE_PREFIX, path),
+        None => ("", path_string),
     };
 
-    shellexpand::tilde(&path_string).to_string()
+    match Path::new(path).is_relative() && !path.starts_with('~') {
+        true => format!("{}{}", prefix, project_dir.join(path).to_string_lossy()),
+        false => path_string.to_string(),
+    }
+}
+
+fn get_log_file_name(name: &str) -> String {
+    format!("{}.log", name.replace('/', "_"))
+}
+
+fn expand_state_file_path(path_string: &str) -> String {
+    // the backend prefix has to be kept out of the way of the tilde expansion
+    match path_string.strip_prefix(SQLITE_STATE_PREFIX) {
+        Some(path) => format!("{}{}", SQLITE_STATE_PREFIX, shellexpand::tilde(path)),
+        None => shellexpand::tilde(path_string).to_string(),
+    }
 }
 
 fn get_state_file_env_key() -> String {
     "EGGSECUTOR_STATE_FILE".to_string()
 }
 
+fn get_namespace_env_key() -> String {
+    "EGGSECUTOR_NAMESPACE".to_string()
+}
+
 fn get_default_state_file_path_string() -> String {
+    xdg::get_state_dir()
+        .join("state")
+        .to_string_lossy()
+        .to_string()
+}
+
+pub fn get_legacy_state_file_path_string() -> String {
     "~/.eggsecutor.state".to_string()
 }
 
@@ -114,7 +523,7 @@ mod tests {
         set_path_to_use(file_path);
         let empty_process_data = "[]";
 
-        let _test_file = TestFile::touch(file_path, &empty_process_data)
+        let _test_file = TestFile::touch(file_path, empty_process_data)
             .expect("test file with process data could not be created");
 
         let processes = get_running_processes_from_state_file()
@@ -140,7 +549,7 @@ mod tests {
         let empty_process_data = "[]";
         set_path_to_use(file_path);
 
-        let _test_file = TestFile::touch(file_path, &empty_process_data)
+        let _test_file = TestFile::touch(file_path, empty_process_data)
             .expect("test file with process data could not be created");
 
         let processes = get_all_processes_from_state_file()
@@ -175,6 +584,19 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn getting_processes_should_return_empty_vec_if_file_is_blank() {
+        let file_path = &generate_path_string();
+        set_path_to_use(file_path);
+
+        let _test_file = TestFile::touch(file_path, "").expect("test file couldnt be created");
+
+        let processes = get_all_processes_from_state_file()
+            .expect("getting processes from blank file returned unexpected error");
+
+        assert!(processes.is_empty());
+    }
+
     #[test]
     fn file_valid_check_should_err_with_nonexistent_file_path() {
         let nonexistent_file_path = &generate_path_string();
@@ -239,32 +661,190 @@ mod tests {
     }
 
     #[test]
-    fn default_state_file_path_string_should_be_set() {
-        let expected_default_path = "~/.eggsecutor.state";
-        assert_eq!(expected_default_path, &get_default_state_file_path_string());
+    fn default_state_file_path_string_should_be_in_xdg_state_dir() {
+        pin_xdg_state_home();
+
+        assert_eq!(
+            format!("{}/eggsecutor/state", TEST_XDG_STATE_HOME),
+            get_default_state_file_path_string()
+        );
     }
 
     #[test]
-    fn state_file_path_should_return_default_if_env_not_set() {
-        env::remove_var(get_state_file_env_key());
+    fn legacy_state_file_path_string_should_be_set() {
+        let expected_legacy_path = "~/.eggsecutor.state";
+        assert_eq!(expected_legacy_path, &get_legacy_state_file_path_string());
+    }
 
-        let state_file_path = get_state_file_path();
+    #[test]
+    fn state_lock_should_be_reacquirable_after_release() {
+        let first_lock = lock_state().expect("state lock could not be acquired");
+        drop(first_lock);
+
+        assert!(lock_state().is_ok());
+    }
+
+    #[test]
+    fn json_state_file_should_be_written_into_missing_parent_dir() {
+        let dir_path = generate_path_string();
+        let file_path = format!("{}/nested/state", dir_path);
+
+        let result = write_processes_to_json_file(&file_path, &[]);
+        let file_data = fs::read_to_string(&file_path);
+        let _result = fs::remove_dir_all(&dir_path);
 
-        // we have to expand the tilde for the path
-        let expected_path = shellexpand::tilde(&get_default_state_file_path_string()).to_string();
+        assert!(result.is_ok());
+        assert_eq!(file_data.unwrap(), "[]");
+    }
+
+    #[test]
+    fn state_file_path_should_return_default_if_env_not_set() {
+        pin_xdg_state_home();
 
+        // no config file and no project dir, whatever is around the test run
+        let (state_file_path, source) = resolve_base_state_file_path_from(None, None);
+
+        let expected_path = format!("{}/eggsecutor/state", TEST_XDG_STATE_HOME);
         assert_eq!(expected_path, state_file_path);
+        assert_eq!(source, StateSource::Default);
     }
 
     #[test]
     fn state_file_path_should_return_user_set_path_if_env_key_present() {
         let test_path_value = "test-dir";
-        env::set_var(get_state_file_env_key(), test_path_value);
+        set_path_to_use(test_path_value);
 
         let state_file_path = get_state_file_path();
         assert_eq!(test_path_value, state_file_path);
     }
 
+    #[test]
+    fn state_location_should_be_json_without_prefix() {
+        let location = parse_state_location("/tmp/eggsecutor.state");
+        assert_eq!(
+            location,
+            StateLocation::Json("/tmp/eggsecutor.state".to_string())
+        );
+    }
+
+    #[test]
+    fn state_location_should_be_sqlite_with_prefix() {
+        let location = parse_state_location("sqlite:/tmp/eggsecutor.db");
+        assert_eq!(
+            location,
+            StateLocation::Sqlite("/tmp/eggsecutor.db".to_string())
+        );
+    }
+
+    #[test]
+    fn expanding_sqlite_state_path_should_keep_prefix() {
+        let expanded = expand_state_file_path("sqlite:~/eggsecutor.db");
+        let expected_path = format!("sqlite:{}", shellexpand::tilde("~/eggsecutor.db"));
+        assert_eq!(expanded, expected_path);
+    }
+
+    #[test]
+    fn namespace_env_key_should_be_default_value() {
+        let default_env_key = "EGGSECUTOR_NAMESPACE";
+        assert_eq!(default_env_key, &get_namespace_env_key());
+    }
+
+    #[test]
+    fn default_namespace_should_use_base_state_file_path() {
+        let path = get_namespaced_state_file_path("/tmp/eggsecutor.state", DEFAULT_NAMESPACE);
+        assert_eq!(path, "/tmp/eggsecutor.state");
+    }
+
+    #[test]
+    fn namespace_should_be_appended_to_state_file_path() {
+        let path = get_namespaced_state_file_path("/tmp/eggsecutor.state", "web");
+        assert_eq!(path, "/tmp/eggsecutor.state.web");
+
+        let sqlite_path = get_namespaced_state_file_path("sqlite:/tmp/eggsecutor.db", "web");
+        assert_eq!(
+            parse_state_location(&sqlite_path),
+            StateLocation::Sqlite("/tmp/eggsecutor.db.web".to_string())
+        );
+    }
+
+    #[test]
+    fn namespace_should_be_parsed_from_state_file_names() {
+        let base = ".eggsecutor.state";
+        let cases = [
+            (".eggsecutor.state", Some(DEFAULT_NAMESPACE)),
+            (".eggsecutor.state.web", Some("web")),
+            (".eggsecutor.state.api_v2", Some("api_v2")),
+            (".eggsecutor.state.web-journal", None),
+            (".eggsecutor.state.", None),
+            (".eggsecutor.statefile", None),
+            (".bashrc", None),
+        ];
+
+        for (file_name, expected) in cases {
+            assert_eq!(
+                get_namespace_from_file_name(base, file_name),
+                expected.map(|x| x.to_string()),
+                "unexpected namespace for {}",
+                file_name
+            );
+        }
+    }
+
+    #[test]
+    fn namespace_validity_should_only_allow_simple_names() {
+        assert!(is_valid_namespace("web"));
+        assert!(is_valid_namespace("project-1_dev"));
+        assert!(!is_valid_namespace(""));
+        assert!(!is_valid_namespace("../etc"));
+        assert!(!is_valid_namespace("with space"));
+    }
+
+    #[test]
+    fn relative_project_config_path_should_be_resolved_against_project_dir() {
+        let project_dir = Path::new("/srv/app/.eggsecutor");
+
+        assert_eq!(
+            resolve_project_relative_path(project_dir, "custom.state"),
+            "/srv/app/.eggsecutor/custom.state"
+        );
+        assert_eq!(
+            resolve_project_relative_path(project_dir, "sqlite:state.db"),
+            "sqlite:/srv/app/.eggsecutor/state.db"
+        );
+    }
+
+    #[test]
+    fn absolute_project_config_path_should_be_kept_as_is() {
+        let project_dir = Path::new("/srv/app/.eggsecutor");
+
+        for path in [
+            "/var/lib/app.state",
+            "~/app.state",
+            "sqlite:/var/lib/app.db",
+        ] {
+            assert_eq!(resolve_project_relative_path(project_dir, path), path);
+        }
+    }
+
+    #[test]
+    fn log_file_name_should_not_contain_path_separators() {
+        assert_eq!(get_log_file_name("SERVER"), "SERVER.log");
+        assert_eq!(get_log_file_name("bin/server"), "bin_server.log");
+    }
+
+    #[test]
+    fn process_lookup_should_match_by_name_id_and_pid() {
+        let process: ProcessInfo = serde_json::from_str(
+            r#"{"id":"abcd1234","name":"TEST_PROCESS","pid":"0000","status":"Running"}"#,
+        )
+        .expect("process data should be valid");
+
+        assert!(ProcessLookup::Name("TEST_PROCESS").matches(&process));
+        assert!(ProcessLookup::Id("abcd1234").matches(&process));
+        assert!(ProcessLookup::Pid("0000").matches(&process));
+        assert!(!ProcessLookup::Name("0000").matches(&process));
+    }
+
     struct TestFile<'a> {
         path: &'a str,
     }
@@ -292,8 +872,17 @@ mod tests {
         format!("{}.testfile", Uuid::new_v4().to_simple())
     }
 
+    const TEST_XDG_STATE_HOME: &str = "/tmp/eggsecutor-test-state";
+
+    // every test pins the same value, so they can't race each other over it
+    fn pin_xdg_state_home() {
+        env::set_var("XDG_STATE_HOME", TEST_XDG_STATE_HOME);
+    }
+
     fn set_path_to_use(path_str: &str) {
         env::set_var(get_state_file_env_key(), path_str);
+        // an ambient namespace would add a suffix to the path
+        env::remove_var(get_namespace_env_key());
     }
 
     fn get_valid_process_data() -> String {
@@ -0,0 +1,259 @@
+use super::config;
+use super::readiness;
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// a hung check command counts as a failure instead of blocking every other check
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum HealthProbe {
+    Command { command: String },
+    Tcp { port: u16 },
+    Http { port: u16, path: String },
+}
+
+// how a process is checked, kept in its launch spec so restarts keep checking it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HealthCheck {
+    pub probe: HealthProbe,
+    pub interval_secs: u64,
+    pub failure_threshold: u32,
+    #[serde(default)]
+    pub restart: bool,
+}
+
+// the outcome of the latest check, kept alongside the process
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct HealthState {
+    pub passed: bool,
+    pub result: String,
+    pub checked_at: u64,
+    pub consecutive_failures: u32,
+}
+
+impl HealthProbe {
+    pub fn parse_command(command: &str) -> Result<Self, String> {
+        Ok(HealthProbe::Command {
+            command: command.to_string(),
+        })
+    }
+
+    pub fn parse_tcp(port: &str) -> Result<Self, String> {
+        Ok(HealthProbe::Tcp {
+            port: readiness::parse_port(port)?,
+        })
+    }
+
+    pub fn parse_http(target: &str) -> Result<Self, String> {
+        let (port, path) = readiness::parse_http_target(target)?;
+        Ok(HealthProbe::Http { port, path })
+    }
+
+    // Ok and Err both carry a short description of what the check saw
+    pub fn run(&self, cwd: Option<&str>) -> Result<String, String> {
+        match self {
+            HealthProbe::Command { command } => run_command(command, cwd),
+            HealthProbe::Tcp { port } => match readiness::connect_to_local_port(*port) {
+                Some(_) => Ok(format!("TCP port {} accepted a connection", port)),
+                None => Err(format!("TCP port {} refused the connection", port)),
+            },
+            HealthProbe::Http { port, path } => {
+                match readiness::is_http_get_successful(*port, path) {
+                    true => Ok(format!("GET localhost:{}{} returned 2xx", port, path)),
+                    false => Err(format!("GET localhost:{}{} did not return 2xx", port, path)),
+                }
+            }
+        }
+    }
+}
+
+impl HealthCheck {
+    // an interval of 0 would run the checks in a busy loop
+    pub fn parse_interval_secs(value: &str) -> Result<u64, String> {
+        value
+            .parse::<u64>()
+            .ok()
+            .filter(|x| *x > 0)
+            .ok_or_else(|| format!(r#"invalid number of seconds "{}""#, value))
+    }
+
+    // a threshold of 0 would count the process as unhealthy before any check failed
+    pub fn parse_failure_threshold(value: &str) -> Result<u32, String> {
+        value
+            .parse::<u32>()
+            .ok()
+            .filter(|x| *x > 0)
+            .ok_or_else(|| format!(r#"invalid number of retries "{}""#, value))
+    }
+
+    pub fn is_due(&self, state: Option<&HealthState>, now: u64) -> bool {
+        match state {
+            Some(state) => now >= state.checked_at.saturating_add(self.interval_secs),
+            None => true,
+        }
+    }
+
+    pub fn is_unhealthy(&self, state: &HealthState) -> bool {
+        state.consecutive_failures >= self.failure_threshold
+    }
+}
+
+pub fn record_result(
+    previous: Option<HealthState>,
+    result: Result<String, String>,
+    now: u64,
+) -> HealthState {
+    let previous_failures = previous.map(|x| x.consecutive_failures).unwrap_or(0);
+    match result {
+        Ok(result) => HealthState {
+            passed: true,
+            result,
+            checked_at: now,
+            consecutive_failures: 0,
+        },
+        Err(result) => HealthState {
+            passed: false,
+            result,
+            checked_at: now,
+            consecutive_failures: previous_failures + 1,
+        },
+    }
+}
+
+pub fn get_unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or(0)
+}
+
+// through the same shell as `hatch --shell`
+fn run_command(command: &str, cwd: Option<&str>) -> Result<String, String> {
+    let mut check = Command::new(config::load_config().get_shell());
+    check
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(cwd) = cwd {
+        check.current_dir(cwd);
+    }
+    let mut child = check.spawn().map_err(|err| err.to_string())?;
+
+    let started_at = Instant::now();
+    loop {
+        match child.try_wait().map_err(|err| err.to_string())? {
+            Some(status) if status.success() => return Ok(format!("`{}` succeeded", command)),
+            Some(status) => return Err(format!("`{}` failed with {}", command, status)),
+            None if started_at.elapsed() >= COMMAND_TIMEOUT => {
+                let _result = child.kill().and_then(|_| child.wait());
+                return Err(format!(
+                    "`{}` timed out after {}s",
+                    command,
+                    COMMAND_TIMEOUT.as_secs()
+                ));
+            }
+            None => thread::sleep(COMMAND_POLL_INTERVAL),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_probe_should_follow_exit_code() {
+        let passing = HealthProbe::parse_command("true").unwrap();
+        let failing = HealthProbe::parse_command("exit 4").unwrap();
+
+        assert!(passing.run(None).is_ok());
+        let err = failing.run(None).unwrap_err();
+        assert!(err.contains("exit status: 4"), "{}", err);
+    }
+
+    #[test]
+    fn failures_should_accumulate_until_a_pass() {
+        let check = HealthCheck {
+            probe: HealthProbe::Tcp { port: 8000 },
+            interval_secs: 10,
+            failure_threshold: 2,
+            restart: false,
+        };
+
+        let state = record_result(None, Err("refused".to_string()), 100);
+        assert!(!check.is_unhealthy(&state));
+
+        let state = record_result(Some(state), Err("refused".to_string()), 110);
+        assert_eq!(state.consecutive_failures, 2);
+        assert!(check.is_unhealthy(&state));
+
+        let state = record_result(Some(state), Ok("accepted".to_string()), 120);
+        assert_eq!(state.consecutive_failures, 0);
+        assert!(state.passed);
+        assert!(!check.is_unhealthy(&state));
+    }
+
+    #[test]
+    fn check_should_be_due_after_interval() {
+        let check = HealthCheck {
+            probe: HealthProbe::Tcp { port: 8000 },
+            interval_secs: 10,
+            failure_threshold: 1,
+            restart: false,
+        };
+        let state = record_result(None, Ok("accepted".to_string()), 100);
+
+        assert!(check.is_due(None, 100));
+        assert!(!check.is_due(Some(&state), 109));
+        assert!(check.is_due(Some(&state), 110));
+    }
+
+    #[test]
+    fn check_with_largest_interval_should_never_be_due_again() {
+        let check = HealthCheck {
+            probe: HealthProbe::Tcp { port: 8000 },
+            interval_secs: HealthCheck::parse_interval_secs(&u64::MAX.to_string()).unwrap(),
+            failure_threshold: 1,
+            restart: false,
+        };
+        let state = record_result(None, Ok("accepted".to_string()), 100);
+
+        assert!(!check.is_due(Some(&state), u64::MAX - 1));
+    }
+
+    #[test]
+    fn zero_interval_and_retries_should_be_rejected() {
+        assert!(HealthCheck::parse_interval_secs("0").is_err());
+        assert!(HealthCheck::parse_failure_threshold("0").is_err());
+        assert_eq!(HealthCheck::parse_interval_secs("30"), Ok(30));
+        assert_eq!(HealthCheck::parse_failure_threshold("3"), Ok(3));
+    }
+
+    #[test]
+    fn retries_beyond_u32_should_be_rejected_instead_of_truncated() {
+        assert!(HealthCheck::parse_failure_threshold("4294967296").is_err());
+        assert!(HealthCheck::parse_failure_threshold("-1").is_err());
+    }
+
+    #[test]
+    fn health_check_should_round_trip_through_json() {
+        let check = HealthCheck {
+            probe: HealthProbe::parse_http("8000/health").unwrap(),
+            interval_secs: 30,
+            failure_threshold: 3,
+            restart: true,
+        };
+
+        let json = serde_json::to_string(&check).unwrap();
+
+        assert!(json.contains(r#""type":"http""#));
+        assert_eq!(serde_json::from_str::<HealthCheck>(&json).unwrap(), check);
+    }
+}
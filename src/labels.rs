@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+pub type Labels = BTreeMap<String, String>;
+
+// what `label` does with each of its arguments, "KEY=VALUE" sets and "KEY-" removes
+#[derive(Debug, PartialEq)]
+pub enum LabelChange {
+    Set(String, String),
+    Remove(String),
+}
+
+// `--tag KEY=VALUE` matches that value, a bare `--tag KEY` any process with the label
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelSelector {
+    key: String,
+    value: Option<String>,
+}
+
+impl LabelSelector {
+    pub fn matches(&self, labels: &Labels) -> bool {
+        match (labels.get(&self.key), &self.value) {
+            (Some(value), Some(expected)) => value == expected,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+// keys end up in column names and selectors, so they are kept to a safe set
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|x| x.is_ascii_alphanumeric() || ['-', '_', '.', '/'].contains(&x))
+}
+
+pub fn parse_label(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) => Ok((parse_key(key)?, value.to_string())),
+        None => Err(format!(r#""{}" should look like KEY=VALUE"#, value)),
+    }
+}
+
+pub fn parse_label_change(value: &str) -> Result<LabelChange, String> {
+    match value.strip_suffix('-') {
+        Some(key) if !value.contains('=') => Ok(LabelChange::Remove(parse_key(key)?)),
+        _ => parse_label(value)
+            .map(|(key, value)| LabelChange::Set(key, value))
+            .map_err(|_| format!(r#""{}" should look like KEY=VALUE or KEY-"#, value)),
+    }
+}
+
+pub fn parse_label_selector(value: &str) -> Result<LabelSelector, String> {
+    match value.split_once('=') {
+        Some((key, value)) => Ok(LabelSelector {
+            key: parse_key(key)?,
+            value: Some(value.to_string()),
+        }),
+        None => Ok(LabelSelector {
+            key: parse_key(value)?,
+            value: None,
+        }),
+    }
+}
+
+fn parse_key(key: &str) -> Result<String, String> {
+    match is_valid_key(key) {
+        true => Ok(key.to_string()),
+        false => Err(format!(
+            r#"invalid label key "{}", only letters, digits, "-", "_", "." and "/" are allowed"#,
+            key
+        )),
+    }
+}
+
+pub fn apply_label_changes(labels: &mut Labels, changes: &[LabelChange]) {
+    for change in changes {
+        match change {
+            LabelChange::Set(key, value) => labels.insert(key.clone(), value.clone()),
+            LabelChange::Remove(key) => labels.remove(key),
+        };
+    }
+}
+
+// "env=prod,team=api", in key order
+pub fn get_labels_string(labels: &Labels) -> String {
+    labels
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_should_split_on_first_equals_sign() {
+        assert_eq!(
+            parse_label("url=http://x/?a=b"),
+            Ok(("url".to_string(), "http://x/?a=b".to_string()))
+        );
+        assert_eq!(parse_label("env="), Ok(("env".to_string(), String::new())));
+        assert!(parse_label("env").is_err());
+        assert!(parse_label("my env=prod").is_err());
+    }
+
+    #[test]
+    fn label_changes_should_set_and_remove() {
+        let changes: Vec<LabelChange> = ["env=prod", "team=api", "old-", "team=web"]
+            .iter()
+            .map(|x| parse_label_change(x).unwrap())
+            .collect();
+        assert_eq!(changes[2], LabelChange::Remove("old".to_string()));
+
+        let mut labels = Labels::new();
+        labels.insert("old".to_string(), "1".to_string());
+        apply_label_changes(&mut labels, &changes);
+        assert_eq!(get_labels_string(&labels), "env=prod,team=web");
+
+        // a value may end with a dash
+        assert_eq!(
+            parse_label_change("range=1-"),
+            Ok(LabelChange::Set("range".to_string(), "1-".to_string()))
+        );
+        assert!(parse_label_change("-").is_err());
+    }
+
+    #[test]
+    fn label_selector_should_match_value_or_presence() {
+        let mut labels = Labels::new();
+        labels.insert("env".to_string(), "prod".to_string());
+
+        assert!(parse_label_selector("env=prod").unwrap().matches(&labels));
+        assert!(parse_label_selector("env").unwrap().matches(&labels));
+        assert!(!parse_label_selector("env=dev").unwrap().matches(&labels));
+        assert!(!parse_label_selector("team").unwrap().matches(&labels));
+    }
+}
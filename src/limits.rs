@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+
+const UNLIMITED: &str = "unlimited";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitResource {
+    Nofile,
+    As,
+    Cpu,
+    Core,
+    Nproc,
+    Fsize,
+}
+
+// `None` is unlimited, the same as `ulimit` shows it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResourceLimit {
+    pub resource: LimitResource,
+    #[serde(default)]
+    pub soft: Option<u64>,
+    #[serde(default)]
+    pub hard: Option<u64>,
+}
+
+impl LimitResource {
+    pub const ALL: [LimitResource; 6] = [
+        LimitResource::Nofile,
+        LimitResource::As,
+        LimitResource::Cpu,
+        LimitResource::Core,
+        LimitResource::Nproc,
+        LimitResource::Fsize,
+    ];
+
+    pub fn get_arg_name(&self) -> &'static str {
+        match self {
+            LimitResource::Nofile => "limit-nofile",
+            LimitResource::As => "limit-as",
+            LimitResource::Cpu => "limit-cpu",
+            LimitResource::Core => "limit-core",
+            LimitResource::Nproc => "limit-nproc",
+            LimitResource::Fsize => "limit-fsize",
+        }
+    }
+}
+
+impl ResourceLimit {
+    // accepts "1024", "512M", "unlimited" or "SOFT:HARD" made of those
+    pub fn parse(resource: LimitResource, value: &str) -> Result<Self, String> {
+        let (soft, hard) = match value.split_once(':') {
+            Some((soft, hard)) => (parse_limit_value(soft)?, parse_limit_value(hard)?),
+            None => {
+                let limit = parse_limit_value(value)?;
+                (limit, limit)
+            }
+        };
+
+        let is_soft_above_hard = match (soft, hard) {
+            (_, None) => false,
+            (None, Some(_)) => true,
+            (Some(soft), Some(hard)) => soft > hard,
+        };
+        if is_soft_above_hard {
+            return Err(format!(
+                r#"invalid limit "{}", the soft limit is above the hard limit"#,
+                value
+            ));
+        }
+        Ok(ResourceLimit {
+            resource,
+            soft,
+            hard,
+        })
+    }
+}
+
+// runs in the child between fork and exec, so it must not allocate
+pub fn apply_limits(limits: &[ResourceLimit]) -> io::Result<()> {
+    for limit in limits {
+        let resource = match limit.resource {
+            LimitResource::Nofile => libc::RLIMIT_NOFILE,
+            LimitResource::As => libc::RLIMIT_AS,
+            LimitResource::Cpu => libc::RLIMIT_CPU,
+            LimitResource::Core => libc::RLIMIT_CORE,
+            LimitResource::Nproc => libc::RLIMIT_NPROC,
+            LimitResource::Fsize => libc::RLIMIT_FSIZE,
+        };
+        let rlimit = libc::rlimit {
+            rlim_cur: to_rlim(limit.soft),
+            rlim_max: to_rlim(limit.hard),
+        };
+        if unsafe { libc::setrlimit(resource, &rlimit) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+fn to_rlim(limit: Option<u64>) -> libc::rlim_t {
+    limit
+        .map(|x| x as libc::rlim_t)
+        .unwrap_or(libc::RLIM_INFINITY)
+}
+
+fn parse_limit_value(value: &str) -> Result<Option<u64>, String> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case(UNLIMITED) {
+        return Ok(None);
+    }
+    parse_size(value).map(Some).map_err(|_| {
+        format!(
+            r#"invalid limit "{}", expected a number, e.g. 1024 or 512M, or "unlimited""#,
+            value
+        )
+    })
+}
+
+// accepts plain numbers or ones with a K, M or G suffix, e.g. 512M
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((index, 'K')) | Some((index, 'k')) => (&value[..index], 1 << 10),
+        Some((index, 'M')) | Some((index, 'm')) => (&value[..index], 1 << 20),
+        Some((index, 'G')) | Some((index, 'g')) => (&value[..index], 1 << 30),
+        _ => (value, 1),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|x| x.checked_mul(multiplier))
+        .ok_or_else(|| format!(r#"invalid size "{}", expected e.g. 1024 or 512M"#, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    #[test]
+    fn single_value_should_set_soft_and_hard_limits() {
+        let limit = ResourceLimit::parse(LimitResource::Nofile, "1024").unwrap();
+
+        assert_eq!(limit.soft, Some(1024));
+        assert_eq!(limit.hard, Some(1024));
+    }
+
+    #[test]
+    fn limit_values_should_accept_suffixes_and_unlimited() {
+        let limit = ResourceLimit::parse(LimitResource::As, "512M:2G").unwrap();
+        assert_eq!(limit.soft, Some(512 * 1024 * 1024));
+        assert_eq!(limit.hard, Some(2 * 1024 * 1024 * 1024));
+
+        let limit = ResourceLimit::parse(LimitResource::Core, "0:unlimited").unwrap();
+        assert_eq!(limit.soft, Some(0));
+        assert_eq!(limit.hard, None);
+    }
+
+    #[test]
+    fn invalid_limits_should_be_err() {
+        for value in ["", "lots", "-1", "10X", "2048:1024", "unlimited:1024"] {
+            assert!(
+                ResourceLimit::parse(LimitResource::Nofile, value).is_err(),
+                "{} should be err",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn limits_should_apply_to_child_process() {
+        let limits = vec![ResourceLimit::parse(LimitResource::Nofile, "64:128").unwrap()];
+        let mut command = Command::new("sh");
+        command.arg("-c").arg("ulimit -Sn; ulimit -Hn");
+        unsafe {
+            command.pre_exec(move || apply_limits(&limits));
+        }
+
+        let output = command.output().unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "64\n128\n");
+    }
+}
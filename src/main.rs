@@ -4,189 +4,1655 @@
 #![allow(deprecated)]
 // our closures actually -> !, so they can't be unwrapped easily
 #![allow(clippy::redundant_closure)]
+// the older tests compare lengths against zero rather than use is_empty
+#![cfg_attr(test, allow(clippy::len_zero))]
 extern crate clap;
 extern crate shellexpand;
 
-use clap::{App, AppSettings};
+use clap::ArgMatches;
+use file_io::ProcessLookup;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
+use std::collections::BTreeMap;
+use std::fs;
 use std::io;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
 use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+mod cgroups;
+mod columns;
+mod completions;
+mod config;
+mod dependencies;
+mod environment;
 mod errors;
+mod export;
 mod file_io;
+mod health;
+mod labels;
+mod limits;
+mod man;
+mod manifest;
+mod migration;
 mod output_display;
+mod proc_stats;
+mod procfile;
+mod project;
+mod readiness;
+mod reaper;
+mod reconcile;
+mod selectors;
+mod signals;
+#[cfg(feature = "sqlite")]
+mod sqlite_state;
 mod subcommands;
+#[cfg(test)]
+mod test_utils;
+mod top;
+mod watch;
+mod xdg;
 
 fn main() {
-    const PROGRAM_TITLE: &str = "eggsecutor";
-    const VERSION: &str = "1.0";
-    const AUTHOR: &str = "astherath <me@felipearce.dev>";
-    const ABOUT: &str = "A friendly background process task manager";
-
-    let mut app = App::new(PROGRAM_TITLE)
-        .version(VERSION)
-        .author(AUTHOR)
-        .about(ABOUT)
-        .setting(AppSettings::ArgRequiredElseHelp);
-
-    app = subcommands::get_all_subcommands()
-        .into_iter()
-        .fold(app, |acc, subcommand| acc.subcommand(subcommand));
-    let matches = app.get_matches();
+    let app = subcommands::get_app().subcommand(subcommands::get_process_identifiers_subcommand());
+    let matches = app.clone().try_get_matches().unwrap_or_else(|err| {
+        // whatever could be parsed still says how the error should be printed
+        if let Ok(matches) = app.ignore_errors(true).try_get_matches() {
+            set_output_options_from_matches(&matches);
+        }
+        errors::handle_invalid_arguments_error(err)
+    });
+
+    // set first, so that every error below is printed in the requested format
+    set_output_options_from_matches(&matches);
+
+    migration::migrate_legacy_files()
+        .unwrap_or_else(|err| errors::handle_legacy_migration_error(err));
+
+    if let Some(namespace) = matches.value_of("namespace") {
+        file_io::set_current_namespace(namespace);
+    }
 
     // get matches and execute commands here
     if let Some(matches) = matches.subcommand_matches("hatch") {
-        if let Some(filename) = matches.value_of("file") {
-            process_file_input_for_hatch_subcommand(filename).unwrap();
-        }
+        let options = HatchOptions {
+            after: matches
+                .values_of("after")
+                .unwrap_or_default()
+                .map(|x| x.to_string())
+                .collect(),
+            readiness: get_readiness_from_matches(matches),
+            labels: matches
+                .values_of("tag")
+                .unwrap_or_default()
+                .map(|x| {
+                    labels::parse_label(x).unwrap_or_else(|err| {
+                        errors::handle_invalid_option_value_error("tag", &err)
+                    })
+                })
+                .collect(),
+            ..HatchOptions::default()
+        };
+        let spec = get_launch_spec_from_matches(matches);
+        let name = matches.value_of("name");
+        let process = match (matches.value_of("shell"), matches.value_of("file")) {
+            (Some(command), _) => {
+                process_shell_input_for_hatch_subcommand(name.unwrap(), command, spec, &options)
+            }
+            (None, Some(filename)) => {
+                process_file_input_for_hatch_subcommand(filename, name, spec, &options)
+            }
+            (None, None) => unreachable!("clap requires either a file or --shell"),
+        };
+        output_display::print_structured_output(
+            &process.unwrap_or_else(|err| errors::handle_io_error(err)),
+        );
     } else if let Some(matches) = matches.subcommand_matches("stop") {
+        let processes = get_confirmed_processes_from_matches(matches, "stop");
+        let results = stop_processes_with_dependents(processes)
+            .unwrap_or_else(|err| errors::handle_io_error(err));
+        output_display::print_structured_output(&results);
+    } else if let Some(matches) = matches.subcommand_matches("list") {
+        let options = get_list_options_from_matches(matches);
+        if matches.is_present("watch") {
+            let watch_options = get_watch_options_from_matches(matches);
+            watch::watch_processes(&options, &watch_options)
+                .unwrap_or_else(|err| errors::handle_io_error(err));
+            std::process::exit(watch::DEATH_EXIT_CODE);
+        } else if matches.is_present("all-namespaces") {
+            print_list_of_processes_in_all_namespaces(&options)
+                .unwrap_or_else(|err| errors::handle_io_error(err));
+        } else {
+            print_list_of_processes(&options).unwrap_or_else(|err| errors::handle_io_error(err));
+        }
+    } else if let Some(matches) = matches.subcommand_matches("restart") {
+        let processes = get_confirmed_processes_from_matches(matches, "restart");
+        let processes: Vec<ProcessInfo> = processes
+            .into_iter()
+            .map(|process| {
+                restart_tracked_process(process).unwrap_or_else(|err| errors::handle_io_error(err))
+            })
+            .collect();
+        output_display::print_structured_output(&processes);
+    } else if let Some(matches) = matches.subcommand_matches("signal") {
+        let signal = matches.value_of("signal").unwrap();
+        let signal = signals::normalize_signal_name(signal)
+            .unwrap_or_else(|| errors::handle_unknown_signal_error(signal));
+        let processes = get_confirmed_processes_from_matches(matches, "signal");
+        let results: Vec<SignalResult> = processes
+            .into_iter()
+            .map(|process| {
+                signal_tracked_process(process, &signal)
+                    .unwrap_or_else(|err| errors::handle_io_error(err))
+            })
+            .collect();
+        output_display::print_structured_output(&results);
+    } else if let Some(matches) = matches.subcommand_matches("label") {
+        let changes: Vec<labels::LabelChange> = matches
+            .values_of("labels")
+            .unwrap()
+            .map(|x| {
+                labels::parse_label_change(x)
+                    .unwrap_or_else(|err| errors::handle_invalid_option_value_error("labels", &err))
+            })
+            .collect();
+        let processes = get_confirmed_processes_from_matches(matches, "label");
+        let processes: Vec<ProcessInfo> = processes
+            .into_iter()
+            .map(|process| {
+                label_tracked_process(process, &changes)
+                    .unwrap_or_else(|err| errors::handle_io_error(err))
+            })
+            .collect();
+        output_display::print_structured_output(&processes);
+    } else if let Some(matches) = matches.subcommand_matches("completions") {
+        let shell = matches.value_of("shell").unwrap();
+        print!(
+            "{}",
+            completions::get_completions_script(shell, &mut subcommands::get_app())
+        );
+    } else if let Some(matches) = matches.subcommand_matches("man") {
+        let app = subcommands::get_app();
+        if let Some(dir) = matches.value_of("dir") {
+            let count = man::write_all_man_pages(&app, Path::new(dir))
+                .unwrap_or_else(|err| errors::handle_man_dir_error(dir, err));
+            output_display::print_man_pages_written_message(count, dir);
+            return;
+        }
+        let page = match matches.value_of("subcommand") {
+            Some(name) => man::get_subcommand_man_page(&app, name)
+                .unwrap_or_else(|| errors::handle_unknown_man_page_error(name)),
+            None => man::get_program_man_page(&app),
+        };
+        print!("{}", page);
+    } else if matches
+        .subcommand_matches(subcommands::PROCESS_IDENTIFIERS_SUBCOMMAND_NAME)
+        .is_some()
+    {
+        let processes = file_io::get_running_processes_from_state_file().unwrap_or_default();
+        for identifier in completions::get_process_identifiers(&processes) {
+            println!("{}", identifier);
+        }
+    } else if matches.subcommand_matches("top").is_some() {
+        top::run_top().unwrap_or_else(|err| errors::handle_terminal_error(err));
+    } else if let Some(matches) = matches.subcommand_matches("inspect") {
         if let Some(process_identifier) = matches.value_of("process identifier") {
-            stop_process_by_process_identifier(process_identifier).unwrap();
+            print_process_inspection(process_identifier);
         }
-    } else if matches.subcommand_matches("list").is_some() {
-        print_list_of_processes().unwrap();
     } else if let Some(matches) = matches.subcommand_matches("clear") {
-        if matches.is_present("only-clear") {
-            clear_all_processes_from_file().unwrap();
-        } else {
-            stop_and_clear_all_processes().unwrap();
+        let results = match matches.is_present("only-clear") {
+            true => untrack_all_processes().unwrap_or_else(|err| errors::handle_io_error(err)),
+            false => {
+                stop_and_clear_all_processes().unwrap_or_else(|err| errors::handle_io_error(err))
+            }
+        };
+        output_display::print_structured_output(&results);
+    } else if matches.subcommand_matches("namespaces").is_some() {
+        print_list_of_namespaces().unwrap_or_else(|err| errors::handle_io_error(err));
+    } else if matches.subcommand_matches("where").is_some() {
+        print_active_state_location();
+    } else if let Some(matches) = matches.subcommand_matches("export") {
+        let format = match matches.value_of("format") {
+            Some(format) => export::ExportFormat::from_name(format),
+            None => matches
+                .value_of("file")
+                .map(export::ExportFormat::from_file_path)
+                .unwrap_or(export::ExportFormat::Json),
+        };
+        export_processes(matches.value_of("file"), format)
+            .unwrap_or_else(|err| errors::handle_io_error(err));
+    } else if let Some(matches) = matches.subcommand_matches("import") {
+        if let Some(filename) = matches.value_of("file") {
+            let on_conflict =
+                export::ConflictStrategy::from_name(matches.value_of("on-conflict").unwrap());
+            let results = import_processes(filename, on_conflict, matches.is_present("dry-run"))
+                .unwrap_or_else(|err| errors::handle_io_error(err));
+            output_display::print_structured_output(&results);
         }
+    } else if let Some(matches) = matches.subcommand_matches("up") {
+        let names: Vec<&str> = matches.values_of("names").unwrap_or_default().collect();
+        let processes = match matches.value_of("procfile") {
+            Some(filename) => {
+                load_procfile_processes(filename, matches.value_of("formation"), &names)
+            }
+            None => load_manifest_processes(matches.value_of("file").unwrap(), &names),
+        };
+        let results = bring_up_manifest_processes(processes)
+            .unwrap_or_else(|err| errors::handle_io_error(err));
+        output_display::print_structured_output(&results);
+    } else if let Some(matches) = matches.subcommand_matches("down") {
+        let names: Vec<&str> = matches.values_of("names").unwrap_or_default().collect();
+        let results = match matches.value_of("procfile") {
+            Some(filename) => bring_down_procfile_processes(filename, &names)
+                .unwrap_or_else(|err| errors::handle_io_error(err)),
+            None => bring_down_manifest_processes(load_manifest_processes(
+                matches.value_of("file").unwrap(),
+                &names,
+            ))
+            .unwrap_or_else(|err| errors::handle_io_error(err)),
+        };
+        output_display::print_structured_output(&results);
+    } else if let Some(matches) = matches.subcommand_matches("check") {
+        check_process_health(matches.is_present("watch"))
+            .unwrap_or_else(|err| errors::handle_io_error(err));
+    } else if let Some(matches) = matches.subcommand_matches("plan") {
+        print_manifest_plan(matches.value_of("file").unwrap());
+    } else if let Some(matches) = matches.subcommand_matches("apply") {
+        let results = apply_manifest_plan(matches.value_of("file").unwrap())
+            .unwrap_or_else(|err| errors::handle_io_error(err));
+        output_display::print_structured_output(&results);
     }
 }
 
-fn process_file_input_for_hatch_subcommand(filename: &str) -> io::Result<()> {
-    if let Err(clap_err) = file_io::check_if_file_is_valid(filename) {
-        clap_err.exit();
+fn set_output_options_from_matches(matches: &ArgMatches) {
+    if let Some(output) = matches.value_of("output") {
+        output_display::set_output_format(output);
+    }
+    if let Some(color) = matches.value_of("color") {
+        output_display::set_color_choice(color);
     }
+}
 
-    hatch_subprocess_from_file(filename)?;
+fn get_launch_spec_from_matches(matches: &ArgMatches) -> LaunchSpec {
+    let cwd = matches.value_of("cwd").map(|cwd| {
+        fs::canonicalize(cwd)
+            .ok()
+            .filter(|x| x.is_dir())
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_else(|| {
+                let err = format!(r#""{}" is not a directory"#, cwd);
+                errors::handle_invalid_option_value_error("cwd", &err)
+            })
+    });
 
-    Ok(())
+    // allowlisted variables are captured now, so restarts don't depend on
+    // whichever environment happens to run them
+    let clear_env = matches.is_present("clear-env");
+    let mut env = BTreeMap::new();
+    if clear_env {
+        for key in matches.values_of("keep-env").unwrap_or_default() {
+            if let Ok(value) = std::env::var(key) {
+                env.insert(key.to_string(), value);
+            }
+        }
+    }
+
+    // explicit --env values win over anything from an env file
+    for path in matches.values_of("env-file").unwrap_or_default() {
+        let env_file = environment::load_env_file(path).unwrap_or_else(|err| {
+            let err = format!(r#"could not load "{}", details: {}"#, path, err);
+            errors::handle_invalid_option_value_error("env-file", &err)
+        });
+        env.extend(env_file);
+    }
+    for assignment in matches.values_of("env").unwrap_or_default() {
+        let (key, value) = environment::parse_env_assignment(assignment)
+            .unwrap_or_else(|err| errors::handle_invalid_option_value_error("env", &err));
+        env.insert(key, value);
+    }
+
+    LaunchSpec {
+        cwd,
+        env,
+        clear_env,
+        health: get_health_check_from_matches(matches),
+        limits: get_resource_limits_from_matches(matches),
+        cgroup_limits: get_cgroup_limits_from_matches(matches),
+        ..LaunchSpec::default()
+    }
 }
 
-fn hatch_subprocess_from_file(filename: &str) -> io::Result<()> {
-    output_display::print_pre_hatch_message(filename);
-    let bin_path = format!("./{}", filename);
-    let child = Command::new(bin_path)
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()
-        .unwrap_or_else(|err| errors::handle_spawn_failure(err));
+fn get_cgroup_limits_from_matches(matches: &ArgMatches) -> Option<cgroups::CgroupLimits> {
+    fn parse_arg<T>(
+        matches: &ArgMatches,
+        arg_name: &str,
+        parse: fn(&str) -> Result<T, String>,
+    ) -> Option<T> {
+        let value = matches.value_of(arg_name)?;
+        let limit = parse(value)
+            .unwrap_or_else(|err| errors::handle_invalid_option_value_error(arg_name, &err));
+        Some(limit)
+    }
+
+    let limits = cgroups::CgroupLimits {
+        memory_max: parse_arg(
+            matches,
+            "memory-max",
+            cgroups::CgroupLimits::parse_memory_max,
+        ),
+        cpu_max: parse_arg(matches, "cpu-max", cgroups::CgroupLimits::parse_cpu_max),
+        pids_max: parse_arg(matches, "pids-max", cgroups::CgroupLimits::parse_pids_max),
+    };
+    match limits == cgroups::CgroupLimits::default() {
+        true => None,
+        false => Some(limits),
+    }
+}
+
+fn get_resource_limits_from_matches(matches: &ArgMatches) -> Vec<limits::ResourceLimit> {
+    limits::LimitResource::ALL
+        .iter()
+        .filter_map(|resource| {
+            let arg_name = resource.get_arg_name();
+            let value = matches.value_of(arg_name)?;
+            let limit = limits::ResourceLimit::parse(*resource, value)
+                .unwrap_or_else(|err| errors::handle_invalid_option_value_error(arg_name, &err));
+            Some(limit)
+        })
+        .collect()
+}
+
+fn get_readiness_from_matches(matches: &ArgMatches) -> Option<readiness::Readiness> {
+    type CheckParser = fn(&str) -> Result<readiness::ReadinessCheck, String>;
+    let check_parsers: [(&str, CheckParser); 5] = [
+        ("ready-alive", readiness::ReadinessCheck::parse_alive),
+        ("ready-tcp", readiness::ReadinessCheck::parse_tcp),
+        ("ready-http", readiness::ReadinessCheck::parse_http),
+        ("ready-file", readiness::ReadinessCheck::parse_file),
+        ("ready-log", readiness::ReadinessCheck::parse_log_line),
+    ];
+
+    let mut checks = vec![];
+    for (arg_name, parse) in check_parsers {
+        for value in matches.values_of(arg_name).unwrap_or_default() {
+            let check = parse(value)
+                .unwrap_or_else(|err| errors::handle_invalid_option_value_error(arg_name, &err));
+            checks.push(check);
+        }
+    }
+    if checks.is_empty() {
+        if !matches.is_present("wait-ready") {
+            return None;
+        }
+        checks = readiness::ReadinessCheck::default_checks();
+    }
+
+    let timeout = matches.value_of("timeout").unwrap();
+    let timeout = timeout.parse::<u64>().unwrap_or_else(|_| {
+        let err = format!(r#"invalid timeout "{}", expected seconds"#, timeout);
+        errors::handle_invalid_option_value_error("timeout", &err)
+    });
+    Some(readiness::Readiness {
+        checks,
+        timeout: Duration::from_secs(timeout),
+    })
+}
+
+fn get_health_check_from_matches(matches: &ArgMatches) -> Option<health::HealthCheck> {
+    type ProbeParser = fn(&str) -> Result<health::HealthProbe, String>;
+    let probe_parsers: [(&str, ProbeParser); 3] = [
+        ("health-cmd", health::HealthProbe::parse_command),
+        ("health-tcp", health::HealthProbe::parse_tcp),
+        ("health-http", health::HealthProbe::parse_http),
+    ];
+
+    // the probe args conflict with each other, so at most one is present
+    let &(arg_name, parse) = probe_parsers
+        .iter()
+        .find(|(arg_name, _)| matches.is_present(arg_name))?;
+    let probe = parse(matches.value_of(arg_name).unwrap())
+        .unwrap_or_else(|err| errors::handle_invalid_option_value_error(arg_name, &err));
+
+    fn parse_arg<T>(
+        matches: &ArgMatches,
+        arg_name: &str,
+        parse: fn(&str) -> Result<T, String>,
+    ) -> T {
+        parse(matches.value_of(arg_name).unwrap())
+            .unwrap_or_else(|err| errors::handle_invalid_option_value_error(arg_name, &err))
+    }
+
+    Some(health::HealthCheck {
+        probe,
+        interval_secs: parse_arg(
+            matches,
+            "health-interval",
+            health::HealthCheck::parse_interval_secs,
+        ),
+        failure_threshold: parse_arg(
+            matches,
+            "health-retries",
+            health::HealthCheck::parse_failure_threshold,
+        ),
+        restart: matches.is_present("restart-unhealthy"),
+    })
+}
+
+// `spec` carries everything the hatch args configured, the path is filled in here
+fn process_file_input_for_hatch_subcommand(
+    filename: &str,
+    name: Option<&str>,
+    spec: LaunchSpec,
+    options: &HatchOptions,
+) -> io::Result<ProcessInfo> {
+    if file_io::check_if_file_is_valid(filename).is_err() {
+        errors::handle_invalid_file_path_error();
+    }
+
+    let spec = LaunchSpec {
+        path: fs::canonicalize(filename)?.to_string_lossy().to_string(),
+        ..spec
+    };
+    hatch_after_dependencies(name.unwrap_or(filename), &spec, options)
+}
+
+// the command line is handed to the shell as is, so only the shell itself has to exist
+fn process_shell_input_for_hatch_subcommand(
+    name: &str,
+    command: &str,
+    spec: LaunchSpec,
+    options: &HatchOptions,
+) -> io::Result<ProcessInfo> {
+    let shell = config::load_config().get_shell().to_string();
+    if file_io::check_if_file_is_valid(&shell).is_err() {
+        errors::handle_invalid_file_path_error();
+    }
+
+    let spec = LaunchSpec {
+        path: shell,
+        args: vec!["-c".to_string(), command.to_string()],
+        command: Some(command.to_string()),
+        ..spec
+    };
+    hatch_after_dependencies(name, &spec, options)
+}
+
+fn hatch_after_dependencies(
+    name: &str,
+    spec: &LaunchSpec,
+    options: &HatchOptions,
+) -> io::Result<ProcessInfo> {
+    check_for_dependency_cycle(name, &options.after);
+    wait_for_dependencies(&options.after);
+    hatch_subprocess_with_options(name, spec, options)
+}
+
+fn check_for_dependency_cycle(name: &str, after: &[String]) {
+    let running_processes = file_io::get_running_processes_from_state_file().unwrap_or_default();
+    let mut graph = dependencies::get_dependency_graph(&running_processes);
+    dependencies::add_dependencies(&mut graph, name, after);
+
+    if let Some(cycle) = dependencies::find_cycle(&graph) {
+        errors::handle_dependency_cycle_error(&cycle);
+    }
+}
+
+fn wait_for_dependencies(after: &[String]) {
+    const DEPENDENCY_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+    const DEPENDENCY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    let started_at = Instant::now();
+    for dependency in after {
+        if find_process_by_name(dependency).is_some() {
+            continue;
+        }
+        output_display::print_waiting_for_dependency_message(dependency);
+        while find_process_by_name(dependency).is_none() {
+            if started_at.elapsed() >= DEPENDENCY_WAIT_TIMEOUT {
+                errors::handle_dependency_timeout_error(dependency);
+            }
+            thread::sleep(DEPENDENCY_POLL_INTERVAL);
+        }
+    }
+}
+
+// every process gets its own cgroup when possible, but only a process that asked
+// for cgroup limits warns about not getting one
+fn create_cgroup_for_process(name: &str, id: &str, spec: &LaunchSpec) -> Option<String> {
+    match cgroups::create_process_cgroup(id, spec.cgroup_limits.as_ref()) {
+        Ok((cgroup, warnings)) => {
+            for warning in warnings {
+                output_display::print_cgroup_warning_message(name, &warning);
+            }
+            Some(cgroup)
+        }
+        Err(err) => {
+            if spec.cgroup_limits.is_some() {
+                output_display::print_cgroup_warning_message(name, &err);
+            }
+            None
+        }
+    }
+}
+
+fn hatch_subprocess_from_file(name: &str, spec: &LaunchSpec) -> io::Result<ProcessInfo> {
+    hatch_subprocess_with_options(name, spec, &HatchOptions::default())
+}
+
+fn hatch_subprocess_with_options(
+    name: &str,
+    spec: &LaunchSpec,
+    options: &HatchOptions,
+) -> io::Result<ProcessInfo> {
+    output_display::print_pre_hatch_message(name);
+    let (log_file_path, log_file) =
+        file_io::open_log_file_for_process(name, spec.log_file.as_deref())?;
+    let log_offset = log_file.metadata()?.len();
+
+    // a restarted process keeps its id, so it can still be found by it
+    let (id, restart_count, labels) = match &options.restart_of {
+        Some(previous) => (
+            previous.id.clone(),
+            previous.restart_count + 1,
+            previous.labels.clone(),
+        ),
+        None => (generate_process_id(), 0, options.labels.clone()),
+    };
+
+    let mut command = Command::new(&spec.path);
+    if spec.clear_env {
+        command.env_clear();
+    }
+    command
+        .args(&spec.args)
+        .envs(&spec.env)
+        .env(environment::NAME_ENV_KEY, name)
+        .env(environment::ID_ENV_KEY, &id)
+        .stdout(Stdio::from(log_file.try_clone()?))
+        .stderr(Stdio::from(log_file));
+    if let Some(cwd) = &spec.cwd {
+        command.current_dir(cwd);
+    }
+    let cgroup = create_cgroup_for_process(name, &id, spec);
+    if let Some(cgroup) = &cgroup {
+        let procs_path = cgroups::get_procs_file_path(cgroup);
+        // failing to join is caught after spawning, it mustn't stop the process
+        unsafe {
+            command.pre_exec(move || {
+                let _result = cgroups::join_cgroup(&procs_path);
+                Ok(())
+            });
+        }
+    }
+    if !spec.limits.is_empty() {
+        let resource_limits = spec.limits.clone();
+        // setrlimit is all that runs between fork and exec
+        unsafe {
+            command.pre_exec(move || limits::apply_limits(&resource_limits));
+        }
+    }
+    let record_id = id.clone();
+    let mut child = reaper::spawn(&mut command, move |pid, exit_status| {
+        record_exit_status(&record_id, pid, exit_status)
+    })
+    .unwrap_or_else(|err| errors::handle_spawn_failure(err));
 
     let pid = child.id();
+    let cgroup = cgroup.filter(|cgroup| {
+        let is_placed = cgroups::contains_process(cgroup, &pid.to_string());
+        if !is_placed {
+            let _result = cgroups::remove_cgroup(cgroup);
+            if spec.cgroup_limits.is_some() {
+                let reason = "the process could not be moved into its cgroup";
+                output_display::print_cgroup_warning_message(name, reason);
+            }
+        }
+        is_placed
+    });
     let child_info = ProcessInfo {
-        name: filename.to_string(),
+        id,
+        name: name.to_string(),
         pid: pid.to_string(),
         status: ProcessStatus::Running,
+        log_file: Some(log_file_path.to_string_lossy().to_string()),
+        spec: spec.clone(),
+        after: options.after.clone(),
+        health: None,
+        restart_count,
+        cgroup,
+        started_at: Some(health::get_unix_timestamp()),
+        labels,
+        exit_status: None,
     };
 
-    add_process_to_state_tracker(child_info)
+    add_process_to_state_tracker(child_info.clone())
         .unwrap_or_else(|err| errors::handle_process_boot_error(err));
 
+    if let Some(readiness) = &options.readiness {
+        wait_for_process_readiness(&mut child, &child_info, readiness, log_offset);
+    }
+
     output_display::print_post_hatch_message(pid);
 
-    Ok(())
+    Ok(child_info)
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+// a restarted process keeps its id but not its pid, so the old waiter leaves it alone
+fn record_exit_status(id: &str, pid: u32, exit_status: &str) {
+    let pid = pid.to_string();
+    let processes =
+        file_io::find_processes_in_state_file(ProcessLookup::Id(id)).unwrap_or_default();
+    for mut process in processes.into_iter().filter(|x| x.pid == pid) {
+        process.exit_status = Some(exit_status.to_string());
+        let _result = file_io::update_process_in_state_file(process);
+    }
+}
+
+// exits with an error, and stops tracking the process, if it dies or isn't ready in time
+fn wait_for_process_readiness(
+    child: &mut reaper::ReapedChild,
+    process: &ProcessInfo,
+    readiness: &readiness::Readiness,
+    log_offset: u64,
+) {
+    const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    output_display::print_waiting_for_readiness_message(&process.name);
+    let log_path = process
+        .log_file
+        .as_deref()
+        .map(Path::new)
+        .unwrap_or(Path::new(""));
+    let started_at = Instant::now();
+    let mut passed = vec![false; readiness.checks.len()];
+
+    loop {
+        if let Some(exit_status) = child.try_wait() {
+            stop_process_cgroup(process);
+            let _result = file_io::remove_process_from_state_file(&process.pid);
+            let reason = format!("process {} before it was ready", exit_status);
+            errors::handle_process_boot_error(io::Error::other(reason));
+        }
+
+        for (check, passed) in readiness.checks.iter().zip(passed.iter_mut()) {
+            *passed = *passed || check.is_passing(started_at, log_path, log_offset);
+        }
+        if passed.iter().all(|x| *x) {
+            return;
+        }
+
+        if started_at.elapsed() >= readiness.timeout {
+            // with --shell only the shell is the child, the rest goes with the cgroup
+            let _result = child.kill();
+            stop_process_cgroup(process);
+            let _result = file_io::remove_process_from_state_file(&process.pid);
+            let pending_check = readiness
+                .checks
+                .iter()
+                .zip(passed.iter())
+                .find(|(_, passed)| !**passed)
+                .map(|(check, _)| check.describe())
+                .unwrap_or_default();
+            errors::handle_readiness_timeout_error(&process.name, &pending_check);
+        }
+        thread::sleep(READINESS_POLL_INTERVAL);
+    }
+}
+
+// what `stop` and `clear` report for each process they handled
+#[derive(Serialize, Debug)]
+struct StopResult {
+    name: String,
+    id: String,
+    pid: String,
+    result: StopOutcome,
+}
+
+// what `signal` reports for the process it signalled
+#[derive(Serialize, Debug)]
+struct SignalResult {
+    name: String,
+    id: String,
+    pid: String,
+    signal: String,
+}
+
+// what `up` and `down` report for each process in the manifest or Procfile
+#[derive(Serialize, Debug)]
+struct ManifestResult {
+    name: String,
+    pid: Option<String>,
+    result: ManifestOutcome,
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum ManifestOutcome {
+    Hatched,
+    AlreadyRunning,
+    Stopped,
+    NotRunning,
+}
+
+// what `plan` and `apply` report for each change, `pid` is that of the running process
+#[derive(Serialize, Debug)]
+struct PlanResult {
+    name: String,
+    action: PlanOutcome,
+    pid: Option<String>,
+    // only `apply` hatches anything
+    hatched_pid: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum PlanOutcome {
+    Start,
+    Restart,
+    Stop,
+}
+
+// what `import` reports for each process in the file
+#[derive(Serialize, Debug)]
+struct ImportResult {
+    name: String,
+    action: ImportOutcome,
+    dry_run: bool,
+    pid: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum ImportOutcome {
+    Hatch,
+    Replace,
+    Skip,
+}
+
+// what `export` reports when it writes to a file, on stdout the export is the result
+#[derive(Serialize, Debug)]
+struct ExportResult {
+    file: String,
+    exported: Vec<String>,
+    skipped: Vec<String>,
+}
+
+// what `check` reports for each process it checked
+#[derive(Serialize, Debug)]
+struct HealthCheckResult {
+    name: String,
+    id: String,
+    pid: String,
+    status: ProcessStatus,
+    health: health::HealthState,
+    restarted: bool,
+}
+
+// what `where` reports about the state it uses
+#[derive(Serialize, Debug)]
+struct StateLocationRecord {
+    state_file: String,
+    reason: String,
+    config_file: String,
+    namespace: String,
+    logs_dir: String,
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum StopOutcome {
+    Stopped,
+    // removed from tracking but left running
+    Untracked,
+}
+
+#[derive(Default)]
+struct HatchOptions {
+    after: Vec<String>,
+    // without readiness checks `hatch` returns as soon as the process is spawned
+    readiness: Option<readiness::Readiness>,
+    labels: labels::Labels,
+    restart_of: Option<ProcessInfo>,
+}
+
+// everything `inspect` shows, the /proc details are missing once it has exited
+#[derive(Serialize, Debug)]
+struct ProcessInspection {
+    #[serde(flatten)]
+    process: ProcessInfo,
+    details: Option<proc_stats::ProcessDetails>,
+    cgroup_usage: Option<cgroups::CgroupUsage>,
+}
+
+struct ListOptions {
+    columns: Vec<columns::Column>,
+    sort: Option<columns::SortOrder>,
+    selector: selectors::Selector,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 enum ProcessStatus {
     Running,
     Stopped,
+    Unhealthy,
+    // died while `list --watch` was looking, it is never stored
+    Exited,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ProcessInfo {
+    // older state files predate process ids
+    #[serde(default)]
+    id: String,
     name: String,
     pid: String,
     status: ProcessStatus,
+    #[serde(default)]
+    log_file: Option<String>,
+    // everything needed to start the process again, e.g. on another machine
+    #[serde(default)]
+    spec: LaunchSpec,
+    // names of the processes that had to be running before this one was hatched
+    #[serde(default)]
+    after: Vec<String>,
+    #[serde(default)]
+    health: Option<health::HealthState>,
+    #[serde(default)]
+    restart_count: u32,
+    // path of the cgroup holding the process and everything it started
+    #[serde(default)]
+    cgroup: Option<String>,
+    // unix timestamp of when it was hatched, or last restarted
+    #[serde(default)]
+    started_at: Option<u64>,
+    // free-form KEY=VALUE pairs for grouping, e.g. by project or environment
+    #[serde(default)]
+    labels: labels::Labels,
+    // how it ended, recorded by the waiter that hatched it
+    #[serde(default)]
+    exit_status: Option<String>,
 }
 
-impl ProcessInfo {
-    fn to_console_string(&self) -> String {
-        format!(
-            "\
-        {:<15} {:<7} {:<10?}\n",
-            self.name, self.pid, self.status
-        )
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct LaunchSpec {
+    path: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    cwd: Option<String>,
+    // when set the child gets nothing but `env`, instead of inheriting ours
+    #[serde(default)]
+    clear_env: bool,
+    #[serde(default)]
+    limits: Vec<limits::ResourceLimit>,
+    #[serde(default)]
+    cgroup_limits: Option<cgroups::CgroupLimits>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    #[serde(default)]
+    log_file: Option<String>,
+    #[serde(default)]
+    stop_signal: Option<String>,
+    #[serde(default)]
+    health: Option<health::HealthCheck>,
+    // the command line given to `hatch --shell`, which `path` and `args` run
+    #[serde(default)]
+    command: Option<String>,
+}
+
+impl LaunchSpec {
+    fn get_stop_signal(&self) -> &str {
+        self.stop_signal
+            .as_deref()
+            .unwrap_or(signals::DEFAULT_STOP_SIGNAL)
     }
 }
 
+pub fn generate_process_id() -> String {
+    Uuid::new_v4().to_simple().to_string()[..8].to_string()
+}
+
 fn add_process_to_state_tracker(process_info: ProcessInfo) -> io::Result<()> {
     file_io::create_state_file_if_not_exists()?;
+    file_io::add_process_to_state_file(process_info)?;
+
+    Ok(())
+}
+
+fn get_list_options_from_matches(matches: &ArgMatches) -> ListOptions {
+    let columns = columns::parse_columns(matches.value_of("columns").unwrap())
+        .unwrap_or_else(|err| errors::handle_invalid_option_value_error("columns", &err));
+    let sort = matches.value_of("sort").map(|value| {
+        columns::SortOrder::parse(value)
+            .unwrap_or_else(|err| errors::handle_invalid_option_value_error("sort", &err))
+    });
+    ListOptions {
+        columns,
+        sort,
+        selector: get_selector_from_matches(matches),
+    }
+}
+
+fn get_watch_options_from_matches(matches: &ArgMatches) -> watch::WatchOptions {
+    let interval = matches.value_of("interval").unwrap();
+    let interval = humantime::parse_duration(interval)
+        .map_err(|err| err.to_string())
+        .and_then(|interval| match interval.is_zero() {
+            true => Err("the interval has to be above zero".to_string()),
+            false => Ok(interval),
+        })
+        .unwrap_or_else(|err| errors::handle_invalid_option_value_error("interval", &err));
+    watch::WatchOptions {
+        interval,
+        exit_on_death: matches.is_present("exit-on-death"),
+    }
+}
 
-    let mut processes = file_io::get_running_processes_from_state_file()?;
+fn get_sorted_process_rows(
+    processes: Vec<ProcessInfo>,
+    options: &ListOptions,
+) -> Vec<columns::ProcessRow> {
+    let processes = options
+        .selector
+        .select(processes, health::get_unix_timestamp());
+    let mut rows = columns::get_process_rows(processes);
+    if let Some(sort) = &options.sort {
+        columns::sort_rows(&mut rows, sort);
+    }
+    rows
+}
+
+fn print_list_of_processes(options: &ListOptions) -> io::Result<()> {
+    let processes = get_alive_processes();
+    let rows = get_sorted_process_rows(processes, options);
 
-    // add new process
-    processes.push(process_info);
+    output_display::print_output(&rows, || {
+        output_display::get_display_output_str_for_processes(&rows, &options.columns)
+    });
+    Ok(())
+}
 
-    // write info
-    file_io::write_processes_to_state_file(processes)?;
+fn print_list_of_processes_in_all_namespaces(options: &ListOptions) -> io::Result<()> {
+    let namespaces = file_io::get_all_namespaces()?;
+    if namespaces.is_empty() {
+        errors::handle_no_file_data_error();
+    }
 
+    let rows_by_namespace: BTreeMap<String, Vec<columns::ProcessRow>> = namespaces
+        .iter()
+        .map(|namespace| {
+            let processes = file_io::get_running_processes_from_namespace(namespace)
+                .unwrap_or_else(|_| errors::handle_no_file_data_error());
+            (
+                namespace.clone(),
+                get_sorted_process_rows(processes, options),
+            )
+        })
+        .collect();
+
+    output_display::print_output(&rows_by_namespace, || {
+        namespaces
+            .iter()
+            .map(|namespace| {
+                format!(
+                    "{}\n{}",
+                    output_display::get_namespace_title_string(namespace),
+                    output_display::get_display_output_str_for_processes(
+                        &rows_by_namespace[namespace],
+                        &options.columns
+                    )
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    });
     Ok(())
 }
 
-fn print_list_of_processes() -> io::Result<()> {
-    let processes = file_io::get_running_processes_from_state_file()
-        .unwrap_or_else(|_| errors::handle_no_file_data_error())
+fn print_process_inspection(process_identifier: &str) {
+    let inspection = get_process_inspection(find_tracked_process(process_identifier));
+    output_display::print_output(&inspection, || {
+        output_display::get_inspect_display_str(&inspection)
+    });
+}
+
+// unlike `stop`, exited processes that are still tracked can be looked at too,
+// a running one wins when a name has been reused
+fn find_tracked_process(process_identifier: &str) -> ProcessInfo {
+    let find_processes = |lookup| {
+        file_io::find_processes_in_state_file(lookup)
+            .unwrap_or_else(|_| errors::handle_no_file_data_error())
+    };
+    let mut processes = find_processes(ProcessLookup::Name(process_identifier));
+    if processes.is_empty() {
+        processes = find_processes(ProcessLookup::Id(process_identifier));
+    }
+    if processes.is_empty() {
+        processes = find_processes(ProcessLookup::Pid(process_identifier));
+    }
+
+    let running = processes.iter().position(|process| {
+        is_process_alive(&process.pid).unwrap_or_else(|err| errors::handle_io_error(err))
+    });
+    match running {
+        Some(index) => processes[index].clone(),
+        None => processes
+            .last()
+            .cloned()
+            .unwrap_or_else(|| errors::handle_untracked_process_error(process_identifier)),
+    }
+}
+
+fn get_process_inspection(process: ProcessInfo) -> ProcessInspection {
+    let details =
+        match is_process_alive(&process.pid).unwrap_or_else(|err| errors::handle_io_error(err)) {
+            true => proc_stats::read_process_details(&process.pid),
+            false => None,
+        };
+    ProcessInspection {
+        cgroup_usage: process.cgroup.as_deref().and_then(cgroups::read_usage),
+        details,
+        process,
+    }
+}
+
+fn print_list_of_namespaces() -> io::Result<()> {
+    let namespaces: Vec<(String, usize)> = file_io::get_all_namespaces()?
         .into_iter()
-        .filter(|process| is_process_alive(&process.pid).unwrap())
+        .map(|namespace| {
+            let count = file_io::get_running_processes_from_namespace(&namespace)
+                .map(|processes| processes.len())
+                .unwrap_or(0);
+            (namespace, count)
+        })
         .collect();
 
-    let display_str_for_processes = output_display::get_display_output_str_for_processes(processes);
-    println!("{}", display_str_for_processes);
+    let namespace_records: Vec<serde_json::Value> = namespaces
+        .iter()
+        .map(|(namespace, count)| serde_json::json!({"namespace": namespace, "processes": count}))
+        .collect();
+    output_display::print_output(&namespace_records, || {
+        output_display::get_display_output_str_for_namespaces(namespaces.clone())
+    });
     Ok(())
 }
 
-fn remove_process_from_state_tracker(pid: &str) -> io::Result<()> {
-    if find_process_by_pid(pid).is_some() {
-        let mut processes = file_io::get_running_processes_from_state_file()?;
-        processes.retain(|x| x.pid != pid);
+fn print_active_state_location() {
+    let (state_file_path, source) = file_io::get_state_file_path_and_source();
+    let config_file_path = config::get_config_file_path();
+    let namespace = file_io::get_current_namespace();
+    let logs_dir = file_io::get_logs_dir();
+    let record = StateLocationRecord {
+        state_file: state_file_path.clone(),
+        reason: output_display::get_state_source_reason_string(&source),
+        config_file: config_file_path.clone(),
+        namespace: namespace.clone(),
+        logs_dir: logs_dir.to_string_lossy().to_string(),
+    };
+    output_display::print_output(&record, || {
+        output_display::get_active_state_display_str(
+            &state_file_path,
+            &source,
+            &config_file_path,
+            &namespace,
+            &logs_dir,
+        )
+    });
+}
+
+fn export_processes(filename: Option<&str>, format: export::ExportFormat) -> io::Result<()> {
+    let processes = file_io::get_running_processes_from_state_file()
+        .unwrap_or_else(|_| errors::handle_no_file_data_error());
 
-        file_io::write_processes_to_state_file(processes)?;
+    let (exported, skipped) = export::get_exported_processes(processes);
+    for name in &skipped {
+        output_display::print_export_skipped_message(name);
+    }
+
+    let exported_names = exported.iter().map(|x| x.name.clone()).collect();
+    let contents = export::serialize_exported_processes(exported, format)
+        .unwrap_or_else(|err| errors::handle_export_file_error(&err));
+    match filename {
+        Some(filename) => {
+            fs::write(filename, contents)?;
+            output_display::print_structured_output(&ExportResult {
+                file: filename.to_string(),
+                exported: exported_names,
+                skipped,
+            });
+        }
+        None => print!("{}", contents),
     }
     Ok(())
 }
 
-fn stop_process_by_process_identifier(process_identifier: &str) -> io::Result<()> {
-    // check if the process identfied passed is actually a pid
-    let pid = &{
-        if let Some(process) = find_process_by_name(process_identifier) {
-            process.pid
-        } else if is_existing_pid(process_identifier) {
-            process_identifier.to_string()
-        } else {
-            errors::handle_no_such_process_error(process_identifier);
+fn import_processes(
+    filename: &str,
+    on_conflict: export::ConflictStrategy,
+    dry_run: bool,
+) -> io::Result<Vec<ImportResult>> {
+    let contents =
+        fs::read_to_string(filename).unwrap_or_else(|err| errors::handle_export_file_error(&err));
+    let exported =
+        export::parse_exported_processes(&contents, export::ExportFormat::from_file_path(filename))
+            .unwrap_or_else(|err| errors::handle_export_file_error(&err));
+
+    // check everything up front so a bad file doesn't leave a half-imported state
+    if !exported
+        .iter()
+        .all(|process| export::is_spec_path_valid(&process.spec))
+    {
+        errors::handle_invalid_file_path_error();
+    }
+
+    let running_names: Vec<String> = file_io::get_running_processes_from_state_file()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|process| process.name)
+        .collect();
+    let actions = export::plan_import(exported, &running_names, on_conflict)
+        .unwrap_or_else(|name| errors::handle_import_name_conflict_error(&name));
+
+    let mut results = vec![];
+    for action in actions {
+        output_display::print_import_action_message(&action, dry_run);
+        let (name, outcome, hatched) = match action {
+            export::ImportAction::Hatch {
+                name,
+                spec,
+                after,
+                labels,
+            } => {
+                let hatched = match dry_run {
+                    true => None,
+                    false => Some(hatch_imported_process(&name, &spec, after, labels)?),
+                };
+                (name, ImportOutcome::Hatch, hatched)
+            }
+            export::ImportAction::Replace {
+                name,
+                spec,
+                after,
+                labels,
+            } => {
+                let hatched = match dry_run {
+                    true => None,
+                    false => {
+                        stop_process_by_process_identifier(&name)?;
+                        Some(hatch_imported_process(&name, &spec, after, labels)?)
+                    }
+                };
+                (name, ImportOutcome::Replace, hatched)
+            }
+            export::ImportAction::Skip { name } => (name, ImportOutcome::Skip, None),
+        };
+        results.push(ImportResult {
+            name,
+            action: outcome,
+            dry_run,
+            pid: hatched.map(|x| x.pid),
+        });
+    }
+    Ok(results)
+}
+
+// processes are exported in the order they were hatched, so dependencies come first
+fn hatch_imported_process(
+    name: &str,
+    spec: &LaunchSpec,
+    after: Vec<String>,
+    labels: labels::Labels,
+) -> io::Result<ProcessInfo> {
+    let options = HatchOptions {
+        after,
+        labels,
+        ..HatchOptions::default()
+    };
+    hatch_after_dependencies(name, spec, &options)
+}
+
+fn load_manifest_processes(filename: &str, names: &[&str]) -> manifest::ManifestProcesses {
+    let processes = manifest::load_manifest(filename)
+        .unwrap_or_else(|err| errors::handle_invalid_manifest_error(filename, &err));
+    manifest::select_processes(processes, names)
+        .unwrap_or_else(|name| errors::handle_unknown_manifest_process_error(filename, &name))
+}
+
+fn load_procfile_entries(filename: &str, names: &[&str]) -> (procfile::ProcfileEntries, String) {
+    let (entries, procfile_dir) = procfile::load_procfile(filename)
+        .unwrap_or_else(|err| errors::handle_invalid_manifest_error(filename, &err));
+    let entries = manifest::select_processes(entries, names)
+        .unwrap_or_else(|name| errors::handle_unknown_manifest_process_error(filename, &name));
+    (entries, procfile_dir)
+}
+
+fn load_procfile_processes(
+    filename: &str,
+    formation: Option<&str>,
+    names: &[&str],
+) -> manifest::ManifestProcesses {
+    let (entries, procfile_dir) = load_procfile_entries(filename, names);
+    procfile::parse_formation(formation.unwrap_or_default())
+        .and_then(|formation| procfile::get_instances(entries, &formation, &procfile_dir))
+        .unwrap_or_else(|err| errors::handle_invalid_manifest_error(filename, &err))
+}
+
+fn bring_up_manifest_processes(
+    processes: manifest::ManifestProcesses,
+) -> io::Result<Vec<ManifestResult>> {
+    file_io::create_state_file_if_not_exists()?;
+    let mut results = vec![];
+    for (name, spec) in processes {
+        let (pid, result) = match find_process_by_name(&name) {
+            Some(process) => {
+                output_display::print_already_running_message(&name);
+                (process.pid, ManifestOutcome::AlreadyRunning)
+            }
+            None => {
+                let process = hatch_subprocess_from_file(&name, &spec)?;
+                (process.pid, ManifestOutcome::Hatched)
+            }
+        };
+        results.push(ManifestResult {
+            name,
+            pid: Some(pid),
+            result,
+        });
+    }
+    Ok(results)
+}
+
+fn bring_down_manifest_processes(
+    processes: manifest::ManifestProcesses,
+) -> io::Result<Vec<ManifestResult>> {
+    file_io::create_state_file_if_not_exists()?;
+    let mut results = vec![];
+    for (name, _) in processes {
+        match find_process_by_name(&name) {
+            Some(process) => {
+                stop_process_by_process_identifier(&name)?;
+                results.push(get_manifest_result(&process, ManifestOutcome::Stopped));
+            }
+            None => {
+                output_display::print_not_running_message(&name);
+                results.push(ManifestResult {
+                    name,
+                    pid: None,
+                    result: ManifestOutcome::NotRunning,
+                });
+            }
         }
+    }
+    Ok(results)
+}
+
+fn get_manifest_result(process: &ProcessInfo, result: ManifestOutcome) -> ManifestResult {
+    ManifestResult {
+        name: process.name.clone(),
+        pid: Some(process.pid.clone()),
+        result,
+    }
+}
+
+// every running instance is stopped, whatever formation it was brought up with
+fn bring_down_procfile_processes(
+    filename: &str,
+    names: &[&str],
+) -> io::Result<Vec<ManifestResult>> {
+    let (entries, _) = load_procfile_entries(filename, names);
+    file_io::create_state_file_if_not_exists()?;
+    let running_processes = file_io::get_running_processes_from_state_file()?;
+
+    let mut results = vec![];
+    for (entry_name, _) in entries {
+        let instances: Vec<&ProcessInfo> = running_processes
+            .iter()
+            .filter(|process| procfile::is_instance_of(&process.name, &entry_name))
+            .collect();
+        if instances.is_empty() {
+            output_display::print_not_running_message(&entry_name);
+            results.push(ManifestResult {
+                name: entry_name,
+                pid: None,
+                result: ManifestOutcome::NotRunning,
+            });
+            continue;
+        }
+        for process in instances {
+            stop_tracked_process(process)?;
+            results.push(get_manifest_result(process, ManifestOutcome::Stopped));
+        }
+    }
+    Ok(results)
+}
+
+fn get_manifest_plan(filename: &str) -> Vec<reconcile::PlanAction> {
+    let desired = load_manifest_processes(filename, &[]);
+    let running = file_io::get_running_processes_from_state_file().unwrap_or_default();
+    reconcile::plan_changes(desired, &running)
+}
+
+fn print_manifest_plan(filename: &str) {
+    let actions = get_manifest_plan(filename);
+    let results: Vec<PlanResult> = actions.iter().map(get_plan_result).collect();
+    output_display::print_output(&results, || output_display::get_plan_display_str(&actions));
+}
+
+fn apply_manifest_plan(filename: &str) -> io::Result<Vec<PlanResult>> {
+    let actions = get_manifest_plan(filename);
+    output_display::print_plan_summary_message(&actions);
+
+    let mut results = vec![];
+    for action in actions {
+        output_display::print_plan_action_message(&action);
+        let mut result = get_plan_result(&action);
+        let hatched = match action {
+            reconcile::PlanAction::Start { name, spec } => {
+                Some(hatch_subprocess_from_file(&name, &spec)?)
+            }
+            reconcile::PlanAction::Restart { process, spec } => {
+                stop_tracked_process(&process)?;
+                Some(hatch_subprocess_from_file(&process.name, &spec)?)
+            }
+            reconcile::PlanAction::Stop { process } => {
+                stop_tracked_process(&process)?;
+                None
+            }
+        };
+        result.hatched_pid = hatched.map(|x| x.pid);
+        results.push(result);
+    }
+    Ok(results)
+}
+
+fn get_plan_result(action: &reconcile::PlanAction) -> PlanResult {
+    let (name, action, pid) = match action {
+        reconcile::PlanAction::Start { name, .. } => (name.clone(), PlanOutcome::Start, None),
+        reconcile::PlanAction::Restart { process, .. } => (
+            process.name.clone(),
+            PlanOutcome::Restart,
+            Some(process.pid.clone()),
+        ),
+        reconcile::PlanAction::Stop { process } => (
+            process.name.clone(),
+            PlanOutcome::Stop,
+            Some(process.pid.clone()),
+        ),
     };
+    PlanResult {
+        name,
+        action,
+        pid,
+        hatched_pid: None,
+    }
+}
 
-    stop_process_by_pid(pid)?;
-    remove_process_from_state_tracker(pid)?;
+fn remove_process_from_state_tracker(pid: &str) -> io::Result<()> {
+    if find_process_by_pid(pid).is_some() {
+        file_io::remove_process_from_state_file(pid)?;
+    }
     Ok(())
 }
 
-fn find_process_by_name(name: &str) -> Option<ProcessInfo> {
-    for process in file_io::get_running_processes_from_state_file().unwrap() {
-        if process.name == name {
-            return Some(process);
+fn get_selector_from_matches(matches: &ArgMatches) -> selectors::Selector {
+    let older_than = matches.value_of("older-than").map(|older_than| {
+        humantime::parse_duration(older_than).unwrap_or_else(|err| {
+            errors::handle_invalid_option_value_error("older-than", &err.to_string())
+        })
+    });
+    selectors::Selector {
+        identifier: matches
+            .value_of("process identifier")
+            .map(|x| x.to_string()),
+        statuses: matches
+            .values_of("status")
+            .unwrap_or_default()
+            .map(|x| {
+                selectors::parse_status(x)
+                    .unwrap_or_else(|err| errors::handle_invalid_option_value_error("status", &err))
+            })
+            .collect(),
+        older_than,
+        labels: matches
+            .values_of("tag")
+            .unwrap_or_default()
+            .map(|x| {
+                labels::parse_label_selector(x)
+                    .unwrap_or_else(|err| errors::handle_invalid_option_value_error("tag", &err))
+            })
+            .collect(),
+    }
+}
+
+// the processes `action` applies to, which needs --yes when there's more than one
+fn get_confirmed_processes_from_matches(matches: &ArgMatches, action: &str) -> Vec<ProcessInfo> {
+    let selector = get_selector_from_matches(matches);
+    let processes = selector.select(get_alive_processes(), health::get_unix_timestamp());
+
+    match (&selector.identifier, processes.len()) {
+        (Some(identifier), 0) if selector.is_single_identifier() => {
+            errors::handle_no_such_process_error(identifier)
+        }
+        (_, 0) => errors::handle_no_matching_processes_error(),
+        (_, 1) => {}
+        (_, count) => {
+            output_display::print_matched_processes_message(&processes);
+            if !matches.is_present("yes") {
+                errors::handle_confirmation_required_error(action, count);
+            }
         }
     }
-    None
+    processes
 }
 
-fn find_process_by_pid(pid: &str) -> Option<ProcessInfo> {
-    for process in file_io::get_running_processes_from_state_file().unwrap() {
-        if process.pid == pid {
-            return Some(process);
+fn get_alive_processes() -> Vec<ProcessInfo> {
+    file_io::get_running_processes_from_state_file()
+        .unwrap_or_else(|_| errors::handle_no_file_data_error())
+        .into_iter()
+        .filter(|process| {
+            is_process_alive(&process.pid).unwrap_or_else(|err| errors::handle_io_error(err))
+        })
+        .collect()
+}
+
+fn find_process_by_process_identifier(process_identifier: &str) -> ProcessInfo {
+    // check if the process identfied passed is actually a pid
+    find_process_by_name(process_identifier)
+        .or_else(|| find_process_by_id(process_identifier))
+        .or_else(|| match is_existing_pid(process_identifier) {
+            true => find_process_by_pid(process_identifier),
+            false => None,
+        })
+        .unwrap_or_else(|| errors::handle_no_such_process_error(process_identifier))
+}
+
+fn stop_process_by_process_identifier(process_identifier: &str) -> io::Result<Vec<StopResult>> {
+    let process = find_process_by_process_identifier(process_identifier);
+    stop_processes_with_dependents(vec![process])
+}
+
+fn stop_processes_with_dependents(processes: Vec<ProcessInfo>) -> io::Result<Vec<StopResult>> {
+    // anything hatched after these processes depends on them, so it has to go first
+    let running_processes = file_io::get_running_processes_from_state_file()?;
+    let graph = dependencies::get_dependency_graph(&running_processes);
+    let dependents: Vec<String> = processes
+        .iter()
+        .flat_map(|process| dependencies::get_dependents(&graph, &process.name))
+        .collect();
+    let dependent_processes: Vec<ProcessInfo> = running_processes
+        .into_iter()
+        .filter(|x| dependents.contains(&x.name) && processes.iter().all(|y| x.pid != y.pid))
+        .collect();
+    let mut stopped_processes = get_processes_in_stop_order(dependent_processes);
+    stopped_processes.extend(get_processes_in_stop_order(processes));
+    for process in &stopped_processes {
+        stop_tracked_process(process)?;
+    }
+    Ok(get_stop_results(&stopped_processes, StopOutcome::Stopped))
+}
+
+fn get_processes_in_stop_order(mut processes: Vec<ProcessInfo>) -> Vec<ProcessInfo> {
+    let stop_order = dependencies::get_stop_order(&dependencies::get_dependency_graph(&processes));
+    processes.sort_by_key(|process| stop_order.iter().position(|x| *x == process.name));
+    processes
+}
+
+// with --watch, checks keep running on each process's own interval until interrupted
+fn check_process_health(watch: bool) -> io::Result<()> {
+    const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    loop {
+        let now = health::get_unix_timestamp();
+        let processes = file_io::get_running_processes_from_state_file()?;
+        let mut checked_count = 0;
+        let mut results = vec![];
+        for process in processes {
+            let check = match &process.spec.health {
+                Some(check) => check.clone(),
+                None => continue,
+            };
+            checked_count += 1;
+            if !watch || check.is_due(process.health.as_ref(), now) {
+                let result = run_health_check(process, &check, now)?;
+                // with --watch each result is printed as it comes, like `list --watch`
+                match watch {
+                    true => output_display::print_structured_output_document(&result),
+                    false => results.push(result),
+                }
+            }
         }
+
+        if !watch {
+            if checked_count == 0 {
+                output_display::print_no_health_checks_message();
+            }
+            output_display::print_structured_output(&results);
+            return Ok(());
+        }
+        thread::sleep(WATCH_POLL_INTERVAL);
+    }
+}
+
+fn run_health_check(
+    mut process: ProcessInfo,
+    check: &health::HealthCheck,
+    now: u64,
+) -> io::Result<HealthCheckResult> {
+    let result = check.probe.run(process.spec.cwd.as_deref());
+    let state = health::record_result(process.health.take(), result, now);
+    let is_unhealthy = check.is_unhealthy(&state);
+    process.status = match is_unhealthy {
+        true => ProcessStatus::Unhealthy,
+        false => ProcessStatus::Running,
+    };
+    output_display::print_health_check_message(&process.name, &state, check.failure_threshold);
+    process.health = Some(state.clone());
+    file_io::update_process_in_state_file(process.clone())?;
+
+    let result = HealthCheckResult {
+        name: process.name.clone(),
+        id: process.id.clone(),
+        pid: process.pid.clone(),
+        status: process.status.clone(),
+        health: state,
+        restarted: is_unhealthy && check.restart,
+    };
+    if result.restarted {
+        restart_tracked_process(process)?;
     }
-    None
+    Ok(result)
+}
+
+fn restart_tracked_process(process: ProcessInfo) -> io::Result<ProcessInfo> {
+    const EXIT_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+    const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    stop_tracked_process(&process)?;
+
+    // the replacement may need whatever the old process was holding, e.g. its port
+    let started_at = Instant::now();
+    while is_process_alive(&process.pid)? && started_at.elapsed() < EXIT_WAIT_TIMEOUT {
+        thread::sleep(EXIT_POLL_INTERVAL);
+    }
+
+    let options = HatchOptions {
+        after: process.after.clone(),
+        restart_of: Some(process.clone()),
+        ..HatchOptions::default()
+    };
+    hatch_subprocess_with_options(&process.name, &process.spec, &options)
+}
+
+fn signal_tracked_process(process: ProcessInfo, signal: &str) -> io::Result<SignalResult> {
+    send_signal_to_pid(&process.pid, signal)?;
+    output_display::print_signal_sent_message(&process.pid, signal);
+    Ok(SignalResult {
+        name: process.name,
+        id: process.id,
+        pid: process.pid,
+        signal: signal.to_string(),
+    })
+}
+
+fn label_tracked_process(
+    mut process: ProcessInfo,
+    changes: &[labels::LabelChange],
+) -> io::Result<ProcessInfo> {
+    labels::apply_label_changes(&mut process.labels, changes);
+    file_io::update_process_in_state_file(process.clone())?;
+    output_display::print_labels_updated_message(&process.name, &process.labels);
+    Ok(process)
+}
+
+fn stop_tracked_process(process: &ProcessInfo) -> io::Result<()> {
+    stop_process(process)?;
+    remove_process_from_state_tracker(&process.pid)?;
+    Ok(())
+}
+
+fn stop_process(process: &ProcessInfo) -> io::Result<()> {
+    stop_process_by_pid(&process.pid, process.spec.get_stop_signal())?;
+    stop_process_cgroup(process);
+    Ok(())
+}
+
+// kills whatever the process left running in its cgroup and removes the cgroup
+fn stop_process_cgroup(process: &ProcessInfo) {
+    if let Some(cgroup) = &process.cgroup {
+        cgroups::stop_cgroup(cgroup, &process.pid).unwrap_or_else(|err| {
+            output_display::print_cgroup_warning_message(&process.name, &err.to_string())
+        });
+    }
+}
+
+fn find_process_by_name(name: &str) -> Option<ProcessInfo> {
+    find_running_process(ProcessLookup::Name(name))
+}
+
+fn find_process_by_id(id: &str) -> Option<ProcessInfo> {
+    find_running_process(ProcessLookup::Id(id))
+}
+
+fn find_process_by_pid(pid: &str) -> Option<ProcessInfo> {
+    find_running_process(ProcessLookup::Pid(pid))
+}
+
+fn find_running_process(lookup: ProcessLookup) -> Option<ProcessInfo> {
+    file_io::find_processes_in_state_file(lookup)
+        .unwrap_or_else(|err| errors::handle_io_error(err))
+        .into_iter()
+        .find(|process| {
+            is_process_alive(&process.pid).unwrap_or_else(|err| errors::handle_io_error(err))
+        })
 }
 
 fn is_existing_pid(pid: &str) -> bool {
     [
         pid.parse::<i32>().is_ok(),
         is_pid_being_tracked(pid),
-        is_process_alive(pid).unwrap(),
+        is_process_alive(pid).unwrap_or_else(|err| errors::handle_io_error(err)),
     ]
     .iter()
     .all(|x| *x)
@@ -194,17 +1660,22 @@ fn is_existing_pid(pid: &str) -> bool {
 
 fn is_pid_being_tracked(pid: &str) -> bool {
     file_io::get_running_processes_from_state_file()
-        .unwrap()
+        .unwrap_or_else(|err| errors::handle_io_error(err))
         .iter()
         .map(|x| &x.pid)
         .any(|x| *x == pid)
 }
 
-fn stop_process_by_pid(pid: &str) -> io::Result<()> {
-    println!("stopping process with pid: {}", pid);
+fn stop_process_by_pid(pid: &str, signal: &str) -> io::Result<()> {
+    output_display::print_stopping_process_message(pid);
+    send_signal_to_pid(pid, signal)
+}
+
+fn send_signal_to_pid(pid: &str, signal: &str) -> io::Result<()> {
     let command = "kill";
     Command::new(command)
-        .arg("15")
+        .arg("-s")
+        .arg(signal)
         .arg(pid)
         .stdout(Stdio::null())
         .stderr(Stdio::null())
@@ -213,12 +1684,32 @@ fn stop_process_by_pid(pid: &str) -> io::Result<()> {
     Ok(())
 }
 
-fn stop_and_clear_all_processes() -> io::Result<()> {
-    file_io::get_running_processes_from_state_file()?
+fn stop_and_clear_all_processes() -> io::Result<Vec<StopResult>> {
+    let processes = get_processes_in_stop_order(file_io::get_running_processes_from_state_file()?);
+    processes
         .iter()
-        .for_each(|x| stop_process_by_pid(&x.pid).unwrap());
+        .for_each(|x| stop_process(x).unwrap_or_else(|err| errors::handle_io_error(err)));
     clear_all_processes_from_file()?;
-    Ok(())
+    Ok(get_stop_results(&processes, StopOutcome::Stopped))
+}
+
+// like `clear --only-clear`, leaves the processes running
+fn untrack_all_processes() -> io::Result<Vec<StopResult>> {
+    let processes = file_io::get_running_processes_from_state_file().unwrap_or_default();
+    clear_all_processes_from_file()?;
+    Ok(get_stop_results(&processes, StopOutcome::Untracked))
+}
+
+fn get_stop_results(processes: &[ProcessInfo], outcome: StopOutcome) -> Vec<StopResult> {
+    processes
+        .iter()
+        .map(|process| StopResult {
+            name: process.name.clone(),
+            id: process.id.clone(),
+            pid: process.pid.clone(),
+            result: outcome,
+        })
+        .collect()
 }
 
 fn clear_all_processes_from_file() -> io::Result<()> {
@@ -0,0 +1,289 @@
+// like subcommands.rs, nothing outside of clap and roff is used here, since the
+// build script includes this file too
+use clap::{App, Arg};
+use roff::{bold, italic, roman, Inline, Roff};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const SECTION: &str = "1";
+const HELP_SUBCOMMAND_NAME: &str = "help";
+
+// returns how many pages were written
+pub fn write_all_man_pages(app: &App, dir: &Path) -> io::Result<usize> {
+    fs::create_dir_all(dir)?;
+    let pages = get_all_man_pages(&get_built_app(app));
+    for (file_name, page) in &pages {
+        fs::write(dir.join(file_name), page)?;
+    }
+    Ok(pages.len())
+}
+
+// the program's page first, then one per subcommand, named the way `man` looks
+// them up, e.g. "eggsecutor-stop.1"
+fn get_all_man_pages(app: &App) -> Vec<(String, String)> {
+    let mut pages = vec![(get_man_page_file_name(app, None), get_man_page(app, None))];
+    for subcommand in get_visible_subcommands(app) {
+        pages.push((
+            get_man_page_file_name(subcommand, Some(app)),
+            get_man_page(subcommand, Some(app)),
+        ));
+    }
+    pages
+}
+
+// hidden subcommands, like the one the completion scripts call, get no page
+pub fn get_subcommand_man_page(app: &App, subcommand_name: &str) -> Option<String> {
+    let app = get_built_app(app);
+    let subcommand = get_visible_subcommands(&app).find(|x| x.get_name() == subcommand_name)?;
+    Some(get_man_page(subcommand, Some(&app)))
+}
+
+pub fn get_program_man_page(app: &App) -> String {
+    get_man_page(&get_built_app(app), None)
+}
+
+// built the way clap does before parsing, so the pages show the same help flags and
+// global options as `--help` does
+fn get_built_app<'help>(app: &App<'help>) -> App<'help> {
+    let mut app = app.clone();
+    app.build();
+    app
+}
+
+fn get_man_page<'help>(app: &App<'help>, parent: Option<&App<'help>>) -> String {
+    let title = get_page_title(app, parent);
+    let program = parent.unwrap_or(app);
+    let footer = format!(
+        "{} {}",
+        program.get_name(),
+        program.get_version().unwrap_or_default()
+    );
+    let args: Vec<&Arg> = app.get_arguments().filter(|x| !x.is_hide_set()).collect();
+
+    let mut page = Roff::new();
+    // without a date, so the pages only change along with the CLI, and quoted since
+    // roff leaves out empty arguments
+    page.control(
+        "TH",
+        [title.to_uppercase().as_str(), SECTION, "\"\"", &footer],
+    );
+
+    page.control("SH", ["NAME"]);
+    let about = app.get_about().unwrap_or_default();
+    page.text([roman(format!("{} - {}", title, about))]);
+
+    page.control("SH", ["SYNOPSIS"]);
+    page.text(get_synopsis(app, parent, &args));
+
+    page.control("SH", ["DESCRIPTION"]);
+    page.text([roman(app.get_long_about().unwrap_or(about))]);
+
+    let options: Vec<&Arg> = args
+        .iter()
+        .copied()
+        .filter(|x| !x.is_positional())
+        .collect();
+    if !options.is_empty() {
+        page.control("SH", ["OPTIONS"]);
+        options.iter().for_each(|x| add_arg_entry(&mut page, x));
+    }
+
+    let positionals: Vec<&Arg> = args.iter().copied().filter(|x| x.is_positional()).collect();
+    if !positionals.is_empty() {
+        page.control("SH", ["ARGUMENTS"]);
+        positionals.iter().for_each(|x| add_arg_entry(&mut page, x));
+    }
+
+    let subcommands: Vec<&App> = get_visible_subcommands(app).collect();
+    if !subcommands.is_empty() {
+        page.control("SH", ["SUBCOMMANDS"]);
+        for subcommand in &subcommands {
+            page.control("TP", []);
+            let reference = get_page_title(subcommand, Some(app));
+            page.text([bold(reference), roman(format!("({})", SECTION))]);
+            page.text([roman(subcommand.get_about().unwrap_or_default())]);
+        }
+    }
+
+    page.control("SH", ["SEE ALSO"]);
+    let see_also: Vec<String> = match parent {
+        Some(parent) => vec![parent.get_name().to_string()],
+        None => subcommands
+            .iter()
+            .map(|x| get_page_title(x, Some(app)))
+            .collect(),
+    };
+    let see_also: Vec<Inline> = see_also
+        .iter()
+        .enumerate()
+        .flat_map(|(i, name)| {
+            let separator = if i == 0 { "" } else { ", " };
+            vec![
+                roman(separator),
+                bold(name),
+                roman(format!("({})", SECTION)),
+            ]
+        })
+        .collect();
+    page.text(see_also);
+
+    if let Some(author) = app.get_author() {
+        page.control("SH", ["AUTHOR"]);
+        page.text([roman(author)]);
+    }
+
+    page.render()
+}
+
+// clap's own `help` subcommand is left out too, the program's page covers it
+fn get_visible_subcommands<'a, 'help>(app: &'a App<'help>) -> impl Iterator<Item = &'a App<'help>> {
+    app.get_subcommands()
+        .filter(|x| !x.is_hide_set() && x.get_name() != HELP_SUBCOMMAND_NAME)
+}
+
+// "eggsecutor-stop", the name `man` knows the page by
+fn get_page_title(app: &App, parent: Option<&App>) -> String {
+    match parent {
+        Some(parent) => format!("{}-{}", parent.get_name(), app.get_name()),
+        None => app.get_name().to_string(),
+    }
+}
+
+fn get_man_page_file_name(app: &App, parent: Option<&App>) -> String {
+    format!("{}.{}", get_page_title(app, parent), SECTION)
+}
+
+// e.g. "eggsecutor stop [--signal SIGNAL] [--all] <PROCESS_IDENTIFIER>"
+fn get_synopsis(app: &App, parent: Option<&App>, args: &[&Arg]) -> Vec<Inline> {
+    let command = match parent {
+        Some(parent) => format!("{} {}", parent.get_name(), app.get_name()),
+        None => app.get_name().to_string(),
+    };
+    let mut synopsis = vec![bold(command)];
+
+    let options = args.iter().filter(|x| !x.is_positional());
+    let positionals = args.iter().filter(|x| x.is_positional());
+    for arg in options.chain(positionals) {
+        let mut usage = get_arg_usage(arg);
+        if arg.is_multiple_occurrences_set() || arg.is_multiple_values_set() {
+            usage.push(roman("..."));
+        }
+        let (open, close) = match arg.is_required_set() {
+            true => ("", ""),
+            false => ("[", "]"),
+        };
+        synopsis.push(roman(format!(" {}", open)));
+        synopsis.extend(usage);
+        synopsis.push(roman(close));
+    }
+
+    if app.has_subcommands() {
+        synopsis.push(roman(" "));
+        synopsis.push(italic("SUBCOMMAND"));
+    }
+    synopsis
+}
+
+// "-s, --signal SIGNAL" for options and "PROCESS_IDENTIFIER" for positionals
+fn get_arg_usage(arg: &Arg) -> Vec<Inline> {
+    let value_names: Vec<&str> = match arg.get_value_names() {
+        Some(names) => names.to_vec(),
+        None if arg.is_takes_value_set() || arg.is_positional() => vec![arg.get_id()],
+        None => vec![],
+    };
+    if arg.is_positional() {
+        return vec![italic(value_names.join(" "))];
+    }
+
+    let flags: Vec<String> = arg
+        .get_short()
+        .map(|x| format!("-{}", x))
+        .into_iter()
+        .chain(arg.get_long().map(|x| format!("--{}", x)))
+        .collect();
+    let mut usage = vec![bold(flags.join(", "))];
+    usage.extend(value_names.iter().map(|x| italic(format!(" {}", x))));
+    usage
+}
+
+fn add_arg_entry(page: &mut Roff, arg: &Arg) {
+    page.control("TP", []);
+    page.text(get_arg_usage(arg));
+
+    let mut description = arg
+        .get_long_help()
+        .or_else(|| arg.get_help())
+        .unwrap_or_default()
+        .to_string();
+    if let Some(values) = arg.get_possible_values() {
+        let names: Vec<&str> = values.iter().map(|x| x.get_name()).collect();
+        description.push_str(&format!(" [possible values: {}]", names.join(", ")));
+    }
+    let defaults: Vec<String> = arg
+        .get_default_values()
+        .iter()
+        .map(|x| x.to_string_lossy().to_string())
+        .collect();
+    if !defaults.is_empty() && !arg.is_hide_default_value_set() {
+        description.push_str(&format!(" [default: {}]", defaults.join(", ")));
+    }
+    page.text([roman(description)]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subcommands;
+
+    #[test]
+    fn every_visible_subcommand_should_get_a_page() {
+        let app = subcommands::get_app();
+        let file_names: Vec<String> = get_all_man_pages(&get_built_app(&app))
+            .into_iter()
+            .map(|(file_name, _)| file_name)
+            .collect();
+
+        assert_eq!(file_names[0], "eggsecutor.1");
+        assert_eq!(
+            file_names.len(),
+            subcommands::get_all_subcommands().len() + 1
+        );
+        assert!(file_names.contains(&"eggsecutor-stop.1".to_string()));
+        assert!(!file_names.iter().any(|x| x.contains("__complete")));
+        assert!(!file_names.contains(&"eggsecutor-help.1".to_string()));
+    }
+
+    #[test]
+    fn subcommand_page_should_document_its_args() {
+        let app = subcommands::get_app();
+        let page = get_subcommand_man_page(&app, "list").unwrap();
+
+        assert!(page.contains(".TH EGGSECUTOR-LIST 1"), "{}", page);
+        assert!(page.contains("eggsecutor\\-list \\- "), "{}", page);
+        assert!(page.contains(".SH OPTIONS"), "{}", page);
+        assert!(page.contains("\\fB\\-\\-columns\\fR"), "{}", page);
+        assert!(
+            page.contains(&format!("[default: {}]", subcommands::DEFAULT_COLUMNS)),
+            "{}",
+            page
+        );
+        assert!(page.contains("\\fBeggsecutor\\fR(1)"), "{}", page);
+    }
+
+    #[test]
+    fn program_page_should_list_subcommands_and_global_options() {
+        let app = subcommands::get_app();
+        let page = get_program_man_page(&app);
+
+        assert!(page.contains(".SH SUBCOMMANDS"), "{}", page);
+        assert!(page.contains("\\fBeggsecutor\\-hatch\\fR(1)"), "{}", page);
+        assert!(
+            page.contains("[possible values: table, json, yaml]"),
+            "{}",
+            page
+        );
+        assert!(get_subcommand_man_page(&app, "__complete").is_none());
+        assert!(get_subcommand_man_page(&app, "stpo").is_none());
+    }
+}
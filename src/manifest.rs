@@ -0,0 +1,211 @@
+use super::signals;
+use super::LaunchSpec;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub type ManifestProcesses = Vec<(String, LaunchSpec)>;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Eggfile {
+    #[serde(default)]
+    processes: BTreeMap<String, ManifestProcess>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestProcess {
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+    cwd: Option<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    log_file: Option<String>,
+    stop_signal: Option<String>,
+}
+
+pub fn load_manifest(path: &str) -> Result<ManifestProcesses, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+    // relative paths in the manifest are relative to the manifest itself
+    let manifest_dir = fs::canonicalize(path)
+        .map_err(|err| err.to_string())?
+        .parent()
+        .map(|x| x.to_path_buf())
+        .unwrap_or_default();
+
+    parse_manifest(&contents, &manifest_dir)
+}
+
+// with no names given every process in the manifest is selected
+pub fn select_processes<T>(
+    processes: Vec<(String, T)>,
+    names: &[&str],
+) -> Result<Vec<(String, T)>, String> {
+    if let Some(unknown) = names
+        .iter()
+        .find(|name| !processes.iter().any(|(x, _)| x == *name))
+    {
+        return Err(unknown.to_string());
+    }
+
+    Ok(processes
+        .into_iter()
+        .filter(|(name, _)| names.is_empty() || names.contains(&name.as_str()))
+        .collect())
+}
+
+fn parse_manifest(contents: &str, manifest_dir: &Path) -> Result<ManifestProcesses, String> {
+    let eggfile: Eggfile = toml::from_str(contents).map_err(|err| err.to_string())?;
+
+    eggfile
+        .processes
+        .into_iter()
+        .map(|(name, process)| {
+            let spec = get_launch_spec(&name, process, manifest_dir)?;
+            Ok((name, spec))
+        })
+        .collect()
+}
+
+fn get_launch_spec(
+    name: &str,
+    process: ManifestProcess,
+    manifest_dir: &Path,
+) -> Result<LaunchSpec, String> {
+    let cwd = match &process.cwd {
+        Some(cwd) => manifest_dir.join(cwd),
+        None => manifest_dir.to_path_buf(),
+    };
+
+    // bare command names are looked up in $PATH when spawned, like a shell would
+    let command = Path::new(&process.command);
+    let path = match process.command.contains('/') {
+        true => cwd
+            .join(command.strip_prefix("./").unwrap_or(command))
+            .to_string_lossy()
+            .to_string(),
+        false => process.command.clone(),
+    };
+
+    let stop_signal = match process.stop_signal {
+        Some(signal) => Some(signals::normalize_signal_name(&signal).ok_or_else(|| {
+            format!(r#"unknown stop_signal "{}" for process "{}""#, signal, name)
+        })?),
+        None => None,
+    };
+
+    Ok(LaunchSpec {
+        path,
+        args: process.args,
+        cwd: Some(cwd.to_string_lossy().to_string()),
+        env: process.env,
+        clear_env: false,
+        log_file: process
+            .log_file
+            .map(|log_file| manifest_dir.join(log_file).to_string_lossy().to_string()),
+        stop_signal,
+        health: None,
+        limits: vec![],
+        cgroup_limits: None,
+        command: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_MANIFEST: &str = r#"
+        [processes.web]
+        command = "./bin/server"
+        args = ["--port", "8000"]
+        cwd = "backend"
+        env = { RUST_LOG = "debug" }
+        log_file = "logs/web.log"
+        stop_signal = "SIGINT"
+
+        [processes.worker]
+        command = "python3"
+    "#;
+
+    #[test]
+    fn manifest_should_parse_into_launch_specs() {
+        let processes = parse_manifest(TEST_MANIFEST, Path::new("/srv/app")).unwrap();
+
+        assert_eq!(processes.len(), 2);
+        let (name, spec) = &processes[0];
+        assert_eq!(name, "web");
+        assert_eq!(spec.path, "/srv/app/backend/bin/server");
+        assert_eq!(spec.args, vec!["--port", "8000"]);
+        assert_eq!(spec.cwd.as_deref(), Some("/srv/app/backend"));
+        assert_eq!(spec.env.get("RUST_LOG").map(|x| x.as_str()), Some("debug"));
+        assert_eq!(spec.log_file.as_deref(), Some("/srv/app/logs/web.log"));
+        assert_eq!(spec.stop_signal.as_deref(), Some("INT"));
+    }
+
+    #[test]
+    fn bare_command_should_be_left_for_path_lookup() {
+        let processes = parse_manifest(TEST_MANIFEST, Path::new("/srv/app")).unwrap();
+
+        let (name, spec) = &processes[1];
+        assert_eq!(name, "worker");
+        assert_eq!(spec.path, "python3");
+        assert_eq!(spec.cwd.as_deref(), Some("/srv/app"));
+        assert!(spec.stop_signal.is_none());
+    }
+
+    #[test]
+    fn unknown_stop_signal_should_be_err() {
+        let contents = r#"
+            [processes.web]
+            command = "server"
+            stop_signal = "NOT_A_SIGNAL"
+        "#;
+
+        let err = parse_manifest(contents, Path::new("/srv/app")).unwrap_err();
+        assert!(err.contains("NOT_A_SIGNAL"));
+    }
+
+    #[test]
+    fn unknown_process_key_should_be_err() {
+        let contents = r#"
+            [processes.web]
+            command = "server"
+            not_a_key = true
+        "#;
+
+        assert!(parse_manifest(contents, Path::new("/srv/app")).is_err());
+    }
+
+    #[test]
+    fn selecting_no_names_should_select_everything() {
+        let processes = parse_manifest(TEST_MANIFEST, Path::new("/srv/app")).unwrap();
+
+        let selected = select_processes(processes, &[]).unwrap();
+
+        assert_eq!(selected.len(), 2);
+    }
+
+    #[test]
+    fn selecting_names_should_filter_processes() {
+        let processes = parse_manifest(TEST_MANIFEST, Path::new("/srv/app")).unwrap();
+
+        let selected = select_processes(processes, &["worker"]).unwrap();
+
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].0, "worker");
+    }
+
+    #[test]
+    fn selecting_unknown_name_should_be_err() {
+        let processes = parse_manifest(TEST_MANIFEST, Path::new("/srv/app")).unwrap();
+
+        let result = select_processes(processes, &["web", "missing"]);
+
+        assert_eq!(result.unwrap_err(), "missing");
+    }
+}
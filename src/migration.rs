@@ -0,0 +1,148 @@
+use super::config;
+use super::file_io;
+use super::xdg;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+type FileMoves = Vec<(PathBuf, PathBuf)>;
+
+// moves the dotfiles older versions kept in $HOME into the XDG directories;
+// anything that already exists at the destination is left untouched
+pub fn migrate_legacy_files() -> io::Result<()> {
+    // once the new state file exists there are no state files left to pick up from $HOME
+    let state_dir = xdg::get_state_dir();
+    let mut file_moves = match state_dir.join("state").exists() {
+        true => vec![],
+        false => {
+            let legacy_state_file_path = expand_path(&file_io::get_legacy_state_file_path_string());
+            get_legacy_state_file_moves(&legacy_state_file_path, &state_dir)?
+        }
+    };
+
+    let legacy_config_file_path = expand_path(&config::get_legacy_config_file_path_string());
+    if legacy_config_file_path.exists() {
+        file_moves.push((
+            legacy_config_file_path,
+            xdg::get_config_dir().join("config.toml"),
+        ));
+    }
+
+    for (from, to) in file_moves {
+        if to.exists() {
+            continue;
+        }
+        move_file(&from, &to)?;
+        eprintln!(
+            r#"moved legacy file "{}" to "{}""#,
+            from.display(),
+            to.display()
+        );
+    }
+    Ok(())
+}
+
+fn get_legacy_state_file_moves(
+    legacy_state_file_path: &Path,
+    state_dir: &Path,
+) -> io::Result<FileMoves> {
+    let legacy_dir = match legacy_state_file_path.parent() {
+        Some(legacy_dir) if legacy_dir.is_dir() => legacy_dir,
+        _ => return Ok(vec![]),
+    };
+    let legacy_file_name = legacy_state_file_path
+        .file_name()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut file_moves = vec![];
+    for entry in fs::read_dir(legacy_dir)? {
+        let file_name = entry?.file_name().to_string_lossy().to_string();
+
+        // namespaced state files keep their suffix in the new location
+        if let Some(namespace) =
+            file_io::get_namespace_from_file_name(&legacy_file_name, &file_name)
+        {
+            let new_file_name = match namespace == file_io::DEFAULT_NAMESPACE {
+                true => "state".to_string(),
+                false => format!("state.{}", namespace),
+            };
+            file_moves.push((legacy_dir.join(file_name), state_dir.join(new_file_name)));
+        }
+    }
+
+    file_moves.sort();
+    Ok(file_moves)
+}
+
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // rename fails across filesystems, e.g. when $HOME and the XDG dirs are on different mounts
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+fn expand_path(path_string: &str) -> PathBuf {
+    PathBuf::from(shellexpand::tilde(path_string).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestDir;
+
+    #[test]
+    fn legacy_state_files_should_map_into_state_dir() {
+        let test_dir = TestDir::new();
+        let home_dir = test_dir.path.join("home");
+        let state_dir = test_dir.path.join("state");
+        fs::create_dir_all(&home_dir).unwrap();
+        for file_name in [".eggsecutor.state", ".eggsecutor.state.web", ".bashrc"] {
+            fs::write(home_dir.join(file_name), "[]").unwrap();
+        }
+
+        let file_moves =
+            get_legacy_state_file_moves(&home_dir.join(".eggsecutor.state"), &state_dir).unwrap();
+
+        assert_eq!(
+            file_moves,
+            vec![
+                (home_dir.join(".eggsecutor.state"), state_dir.join("state")),
+                (
+                    home_dir.join(".eggsecutor.state.web"),
+                    state_dir.join("state.web")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn legacy_state_files_should_be_empty_if_dir_missing() {
+        let test_dir = TestDir::new();
+        let missing_path = test_dir.path.join("missing").join(".eggsecutor.state");
+
+        let file_moves = get_legacy_state_file_moves(&missing_path, &test_dir.path).unwrap();
+
+        assert!(file_moves.is_empty());
+    }
+
+    #[test]
+    fn moving_file_should_create_destination_dir() {
+        let test_dir = TestDir::new();
+        let from = test_dir.path.join(".eggsecutor.state");
+        let to = test_dir.path.join("nested").join("state");
+        fs::create_dir_all(&test_dir.path).unwrap();
+        fs::write(&from, "[]").unwrap();
+
+        move_file(&from, &to).expect("file could not be moved");
+
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(&to).unwrap(), "[]");
+    }
+}
@@ -1,31 +1,779 @@
+use super::cgroups::CgroupLimits;
+use super::columns::{Column, ProcessRow};
+use super::export::ImportAction;
+use super::file_io::StateSource;
+use super::health::{self, HealthState};
+use super::labels::{self, Labels};
+use super::limits::ResourceLimit;
+use super::reconcile::PlanAction;
+use super::{ProcessInfo, ProcessInspection, ProcessStatus};
+use crossterm::style::{Color, Stylize};
+use crossterm::terminal;
+use serde::Serialize;
+use std::env;
+use std::io::{self, IsTerminal};
+use std::iter;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
 
-use super::ProcessInfo;
+const OUTPUT_FORMAT_ENV_KEY: &str = "EGGSECUTOR_OUTPUT";
+const COLOR_ENV_KEY: &str = "EGGSECUTOR_COLOR";
+const ELLIPSIS: char = '…';
 
-pub fn get_display_output_str_for_processes(processes: Vec<ProcessInfo>) -> String {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "table" => Some(OutputFormat::Table),
+            "json" => Some(OutputFormat::Json),
+            "yaml" => Some(OutputFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+}
+
+// like the namespace, this is kept in the environment so it can also be set there
+pub fn set_output_format(name: &str) {
+    env::set_var(OUTPUT_FORMAT_ENV_KEY, name);
+}
+
+// an unknown format falls back to the table, since errors are printed with this too
+pub fn get_output_format() -> OutputFormat {
+    env::var(OUTPUT_FORMAT_ENV_KEY)
+        .ok()
+        .and_then(|x| OutputFormat::from_name(&x))
+        .unwrap_or(OutputFormat::Table)
+}
+
+pub fn set_color_choice(name: &str) {
+    env::set_var(COLOR_ENV_KEY, name);
+}
+
+// NO_COLOR only turns off the automatic colors, asking for them explicitly still works
+fn is_color_enabled() -> bool {
+    let choice = env::var(COLOR_ENV_KEY)
+        .ok()
+        .and_then(|x| ColorChoice::from_name(&x))
+        .unwrap_or(ColorChoice::Auto);
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            env::var_os("NO_COLOR").is_none_or(|x| x.is_empty()) && io::stdout().is_terminal()
+        }
+    }
+}
+
+// piped output is never cut short, so scripts always get whole values
+fn get_terminal_width() -> Option<usize> {
+    match io::stdout().is_terminal() {
+        true => terminal::size().ok().map(|(width, _)| width as usize),
+        false => None,
+    }
+}
+
+pub fn is_structured_output() -> bool {
+    get_output_format() != OutputFormat::Table
+}
+
+pub fn print_output<T: Serialize>(value: &T, get_table_string: impl FnOnce() -> String) {
+    match get_output_format() {
+        OutputFormat::Table => println!("{}", get_table_string()),
+        format => println!("{}", get_structured_output_string(value, format)),
+    }
+}
+
+// for results whose table form is the messages printed while getting them
+pub fn print_structured_output<T: Serialize>(value: &T) {
+    if is_structured_output() {
+        println!(
+            "{}",
+            get_structured_output_string(value, get_output_format())
+        );
+    }
+}
+
+// a single line of JSON, or a YAML document, for output that keeps coming
+pub fn print_structured_output_document<T: Serialize>(value: &T) {
+    match get_output_format() {
+        OutputFormat::Table => {}
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string(value).expect("output should serialize")
+        ),
+        OutputFormat::Yaml => println!(
+            "---\n{}",
+            get_structured_output_string(value, OutputFormat::Yaml)
+        ),
+    }
+}
+
+pub fn print_error_output(code: &str, message: &str) {
+    eprintln!(
+        "{}",
+        get_error_output_string(code, message, get_output_format())
+    );
+}
+
+fn get_error_output_string(code: &str, message: &str, format: OutputFormat) -> String {
+    let error = serde_json::json!({
+        "error": {
+            "code": code,
+            "message": message,
+        }
+    });
+    get_structured_output_string(&error, format)
+}
+
+fn get_structured_output_string<T: Serialize>(value: &T, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Yaml => serde_yaml::to_string(value)
+            .expect("output should serialize")
+            .trim_end()
+            .to_string(),
+        _ => serde_json::to_string_pretty(value).expect("output should serialize"),
+    }
+}
+
+// progress messages move to stderr with structured output, keeping stdout parseable
+fn print_message(message: &str) {
+    match is_structured_output() {
+        true => eprintln!("{}", message),
+        false => println!("{}", message),
+    }
+}
+
+pub fn get_display_output_str_for_processes(rows: &[ProcessRow], columns: &[Column]) -> String {
+    get_display_table_string(rows, columns, &[])
+}
+
+// rows of processes whose status just changed are shown in reverse video
+pub fn get_display_output_str_for_watched_processes(
+    rows: &[ProcessRow],
+    columns: &[Column],
+    changed_ids: &[String],
+) -> String {
+    get_display_table_string(rows, columns, changed_ids)
+}
+
+fn get_display_table_string(
+    rows: &[ProcessRow],
+    columns: &[Column],
+    highlighted_ids: &[String],
+) -> String {
+    let widths = get_column_widths(rows, columns, get_terminal_width());
+    let is_colored = is_color_enabled();
+    format!(
+        "{}\n{}",
+        get_display_header_string(columns, &widths),
+        rows.iter()
+            .map(|row| {
+                let row_string = get_display_process_row_string(row, columns, &widths, is_colored);
+                match highlighted_ids.contains(&row.process.id) {
+                    true => format!("{}\n", row_string.reverse()),
+                    false => format!("{}\n", row_string),
+                }
+            })
+            .collect::<Vec<String>>()
+            .join("")
+    )
+}
+
+// wide enough for every cell, with the widest of the columns that may be cut
+// narrowed until the table fits in `max_width`
+pub fn get_column_widths(
+    rows: &[ProcessRow],
+    columns: &[Column],
+    max_width: Option<usize>,
+) -> Vec<usize> {
+    let mut widths: Vec<usize> = columns
+        .iter()
+        .map(|column| {
+            rows.iter()
+                .map(|row| column.get_cell_string(row).chars().count())
+                .chain(iter::once(column.get_header().chars().count()))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let max_width = match max_width {
+        Some(max_width) => max_width,
+        None => return widths,
+    };
+
+    while get_table_width(&widths) > max_width {
+        let narrowed = columns
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .filter(|(_, (column, &width))| column.get_min_width().is_some_and(|x| width > x))
+            .max_by_key(|(_, (_, &width))| width)
+            .map(|(index, _)| index);
+        match narrowed {
+            Some(index) => widths[index] -= 1,
+            // as narrow as it gets, the terminal wraps the rest
+            None => break,
+        }
+    }
+    widths
+}
+
+fn get_table_width(widths: &[usize]) -> usize {
+    widths.iter().sum::<usize>() + widths.len().saturating_sub(1)
+}
+
+pub fn get_display_process_row_string(
+    row: &ProcessRow,
+    columns: &[Column],
+    widths: &[usize],
+    is_colored: bool,
+) -> String {
+    let cells: Vec<String> = columns
+        .iter()
+        .map(|column| column.get_cell_string(row))
+        .collect();
+    let colors: Vec<Option<Color>> = columns
+        .iter()
+        .map(|column| match (column, is_colored) {
+            (Column::Status, true) => Some(get_status_color(&row.process.status)),
+            _ => None,
+        })
+        .collect();
+    get_display_row_string(&cells, widths, &colors)
+}
+
+fn get_status_color(status: &ProcessStatus) -> Color {
+    match status {
+        ProcessStatus::Running => Color::Green,
+        ProcessStatus::Stopped => Color::Yellow,
+        ProcessStatus::Unhealthy | ProcessStatus::Exited => Color::Red,
+    }
+}
+
+// cells are cut and padded to their column's width before being colored, so the
+// escape codes don't count towards it
+fn get_display_row_string(cells: &[String], widths: &[usize], colors: &[Option<Color>]) -> String {
+    let row = cells
+        .iter()
+        .zip(widths)
+        .zip(colors)
+        .map(|((cell, &width), color)| {
+            let cell = get_truncated_string(cell, width);
+            let padding = " ".repeat(width.saturating_sub(cell.chars().count()));
+            match color {
+                Some(color) => format!("{}{}", cell.with(*color), padding),
+                None => format!("{}{}", cell, padding),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    row.trim_end().to_string()
+}
+
+fn get_truncated_string(value: &str, width: usize) -> String {
+    match value.chars().count() > width {
+        true => value
+            .chars()
+            .take(width.saturating_sub(1))
+            .chain(iter::once(ELLIPSIS))
+            .collect(),
+        false => value.to_string(),
+    }
+}
+
+// the two largest units, e.g. "2d3h", "4m05s" or "42s"
+pub fn get_duration_string(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, _) => format!("{}m{:02}s", minutes, secs % 60),
+        (0, _, _) => format!("{}h{:02}m", hours, minutes),
+        _ => format!("{}d{}h", days, hours),
+    }
+}
+
+pub fn get_inspect_display_str(inspection: &ProcessInspection) -> String {
+    let process = &inspection.process;
+    let details = inspection.details.as_ref();
+    let now = health::get_unix_timestamp();
+    // older state files predate start times, /proc still knows them while it runs
+    let started_at = process
+        .started_at
+        .or_else(|| details.map(|x| now.saturating_sub(x.stats.uptime_secs)));
+
+    let mut fields: Vec<(&str, Vec<String>)> = vec![
+        ("name", vec![process.name.clone()]),
+        ("id", vec![process.id.clone()]),
+        ("pid", vec![process.pid.clone()]),
+        ("status", vec![format!("{:?}", process.status)]),
+        (
+            "labels",
+            process
+                .labels
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect(),
+        ),
+        (
+            "state",
+            vec![details.map_or("exited".to_string(), |x| x.state.clone())],
+        ),
+        (
+            "started",
+            started_at
+                .map(|x| get_timestamp_string(x, now))
+                .into_iter()
+                .collect(),
+        ),
+        ("restarts", vec![process.restart_count.to_string()]),
+        ("command", vec![get_command_string(process)]),
+        ("cwd", process.spec.cwd.iter().cloned().collect()),
+        ("environment", get_environment_strings(process)),
+        ("log", process.log_file.iter().cloned().collect()),
+        (
+            "limits",
+            process.spec.limits.iter().map(get_limit_string).collect(),
+        ),
+    ];
+    if let Some(exit_status) = &process.exit_status {
+        fields.push(("exit status", vec![exit_status.clone()]));
+    }
+    if let Some(cgroup) = &process.cgroup {
+        fields.push(("cgroup", vec![cgroup.clone()]));
+        let cgroup_limits = process.spec.cgroup_limits.as_ref();
+        fields.push((
+            "cgroup limits",
+            cgroup_limits
+                .map(get_cgroup_limit_strings)
+                .unwrap_or_default(),
+        ));
+        fields.push((
+            "cgroup usage",
+            inspection
+                .cgroup_usage
+                .iter()
+                .map(|x| x.describe())
+                .collect(),
+        ));
+    }
+    if let Some(details) = details {
+        fields.extend(vec![
+            (
+                "memory",
+                vec![format!(
+                    "{} resident, {} virtual",
+                    get_size_string(details.stats.rss_bytes),
+                    get_size_string(details.virtual_memory_bytes)
+                )],
+            ),
+            ("cpu", vec![format!("{:.1}%", details.stats.cpu_percent)]),
+            ("threads", vec![details.stats.threads.to_string()]),
+            (
+                "open files",
+                details
+                    .stats
+                    .open_fds
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect(),
+            ),
+            (
+                "children",
+                details
+                    .children
+                    .iter()
+                    .map(|x| format!("{} {}", x.pid, x.command))
+                    .collect(),
+            ),
+            ("listening", details.listening_sockets.clone()),
+        ]);
+    }
+    if let Some(state) = &process.health {
+        let outcome = match state.passed {
+            true => "passing",
+            false => "failing",
+        };
+        fields.push(("health", vec![format!("{}, {}", outcome, state.result)]));
+    }
+    if !process.after.is_empty() {
+        fields.push(("after", process.after.clone()));
+    }
+
+    fields
+        .iter()
+        .map(|(label, values)| get_inspect_field_string(label, values))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// later values go on their own lines, lined up under the first one
+fn get_inspect_field_string(label: &str, values: &[String]) -> String {
+    let label = format!("{}:", label);
+    match values.split_first() {
+        Some((first, rest)) => std::iter::once(format!("{:<15}{}", label, first))
+            .chain(rest.iter().map(|x| format!("{:<15}{}", "", x)))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        None => format!("{:<15}-", label),
+    }
+}
+
+fn get_timestamp_string(timestamp: u64, now: u64) -> String {
+    format!(
+        "{} ({} ago)",
+        humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(timestamp)),
+        get_duration_string(now.saturating_sub(timestamp))
+    )
+}
+
+fn get_command_string(process: &ProcessInfo) -> String {
+    if let Some(command) = &process.spec.command {
+        return command.clone();
+    }
+    std::iter::once(&process.spec.path)
+        .chain(&process.spec.args)
+        .map(|x| match x.contains(char::is_whitespace) || x.is_empty() {
+            true => format!("{:?}", x),
+            false => x.clone(),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn get_environment_strings(process: &ProcessInfo) -> Vec<String> {
+    let mut values: Vec<String> = process
+        .spec
+        .env
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    match process.spec.clear_env {
+        true => values.push("nothing else inherited".to_string()),
+        false if values.is_empty() => values.push("inherited".to_string()),
+        false => {}
+    }
+    values
+}
+
+fn get_limit_string(limit: &ResourceLimit) -> String {
+    let get_value_string = |value: Option<u64>| match value {
+        Some(value) => value.to_string(),
+        None => "unlimited".to_string(),
+    };
+    let resource = limit.resource.get_arg_name().trim_start_matches("limit-");
+    match limit.soft == limit.hard {
+        true => format!("{} {}", resource, get_value_string(limit.soft)),
+        false => format!(
+            "{} {}:{}",
+            resource,
+            get_value_string(limit.soft),
+            get_value_string(limit.hard)
+        ),
+    }
+}
+
+fn get_cgroup_limit_strings(limits: &CgroupLimits) -> Vec<String> {
+    let mut values = vec![];
+    if let Some(memory_max) = limits.memory_max {
+        values.push(format!("memory {}", get_size_string(memory_max)));
+    }
+    if let Some(cpu_max) = limits.cpu_max {
+        values.push(format!("cpu {}", cpu_max));
+    }
+    if let Some(pids_max) = limits.pids_max {
+        values.push(format!("pids {}", pids_max));
+    }
+    values
+}
+
+pub fn get_size_string(bytes: u64) -> String {
+    let units = [("G", 1 << 30), ("M", 1 << 20), ("K", 1 << 10)];
+    units
+        .iter()
+        .find(|&&(_, size)| bytes >= size)
+        .map(|&(unit, size)| format!("{:.1}{}", bytes as f64 / size as f64, unit))
+        .unwrap_or_else(|| format!("{}B", bytes))
+}
+
+pub fn get_display_output_str_for_namespaces(namespaces: Vec<(String, usize)>) -> String {
     format!(
         "{}\n{}",
-        get_display_header_string(),
-        processes
+        get_namespace_display_header_string(),
+        namespaces
             .iter()
-            .map(|x| x.to_console_string())
+            .map(|(namespace, count)| format!("{:<20} {:<10}\n", namespace, count))
             .collect::<Vec<String>>()
             .join("")
     )
 }
 
-fn get_display_header_string() -> String {
+pub fn get_namespace_title_string(namespace: &str) -> String {
+    format!("namespace: {}", namespace)
+}
+
+pub fn get_active_state_display_str(
+    state_file_path: &str,
+    source: &StateSource,
+    config_file_path: &str,
+    namespace: &str,
+    logs_dir: &Path,
+) -> String {
     format!(
-        "{:<15} {:<7} {:<10}\n{:-<35}",
-        "Process name", "pid", "status", ""
+        "{:<11}{}\n{:<11}{}\n{:<11}{}\n{:<11}{}\n{:<11}{}",
+        "state:",
+        state_file_path,
+        "reason:",
+        get_state_source_reason_string(source),
+        "config:",
+        config_file_path,
+        "namespace:",
+        namespace,
+        "logs:",
+        logs_dir.to_string_lossy()
     )
 }
 
+pub fn get_state_source_reason_string(source: &StateSource) -> String {
+    match source {
+        StateSource::EnvVar => "set by the EGGSECUTOR_STATE_FILE environment variable".to_string(),
+        StateSource::ConfigFile(path) => format!(r#"set by "state_file" in config "{}""#, path),
+        StateSource::ProjectDir(path) => format!(r#"found project directory "{}""#, path),
+        StateSource::Default => "no project directory found, using the global default".to_string(),
+    }
+}
+
+fn get_namespace_display_header_string() -> String {
+    format!("{:<20} {:<10}\n{:-<31}", "Namespace", "processes", "")
+}
+
+fn get_display_header_string(columns: &[Column], widths: &[usize]) -> String {
+    format!(
+        "{}\n{:-<width$}",
+        get_display_header_row_string(columns, widths),
+        "",
+        width = get_table_width(widths)
+    )
+}
+
+pub fn get_display_header_row_string(columns: &[Column], widths: &[usize]) -> String {
+    let headers: Vec<String> = columns.iter().map(|x| x.get_header()).collect();
+    get_display_row_string(&headers, widths, &vec![None; columns.len()])
+}
+
 pub fn print_pre_hatch_message(filename: &str) {
-    println!("{}", get_pre_hatch_message_string(filename));
+    print_message(&get_pre_hatch_message_string(filename));
 }
 
 pub fn print_post_hatch_message(pid: u32) {
-    println!("{}", get_post_hatch_message_string(pid));
+    print_message(&get_post_hatch_message_string(pid));
+}
+
+pub fn print_export_skipped_message(name: &str) {
+    eprintln!("{}", get_export_skipped_message_string(name));
+}
+
+pub fn print_import_action_message(action: &ImportAction, dry_run: bool) {
+    print_message(&get_import_action_message_string(action, dry_run));
+}
+
+pub fn print_plan_action_message(action: &PlanAction) {
+    print_message(&get_plan_action_message_string(action));
+}
+
+pub fn get_plan_display_str(actions: &[PlanAction]) -> String {
+    actions
+        .iter()
+        .map(get_plan_action_message_string)
+        .chain(std::iter::once(get_plan_summary_message_string(actions)))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn print_plan_summary_message(actions: &[PlanAction]) {
+    print_message(&get_plan_summary_message_string(actions));
+}
+
+pub fn print_waiting_for_dependency_message(name: &str) {
+    print_message(&get_waiting_for_dependency_message_string(name));
+}
+
+pub fn print_waiting_for_readiness_message(name: &str) {
+    print_message(&get_waiting_for_readiness_message_string(name));
+}
+
+pub fn print_health_check_message(name: &str, state: &HealthState, failure_threshold: u32) {
+    print_message(&get_health_check_message_string(
+        name,
+        state,
+        failure_threshold,
+    ));
+}
+
+pub fn print_no_health_checks_message() {
+    print_message("no tracked process has a health check, add one with `hatch --health-*`");
+}
+
+pub fn print_cgroup_warning_message(name: &str, reason: &str) {
+    eprintln!("{}", get_cgroup_warning_message_string(name, reason));
+}
+
+pub fn print_stopping_process_message(pid: &str) {
+    print_message(&format!("stopping process with pid: {}", pid));
+}
+
+pub fn print_process_died_message(name: &str, pid: &str) {
+    eprintln!(r#"process "{}" with pid {} has exited"#, name, pid);
+}
+
+pub fn print_matched_processes_message(processes: &[ProcessInfo]) {
+    print_message(&get_matched_processes_message_string(processes));
+}
+
+fn get_matched_processes_message_string(processes: &[ProcessInfo]) -> String {
+    let lines: Vec<String> = processes
+        .iter()
+        .map(|x| format!("  {} (id: {}, pid: {})", x.name, x.id, x.pid))
+        .collect();
+    format!("{} processes match:\n{}", processes.len(), lines.join("\n"))
+}
+
+pub fn print_man_pages_written_message(count: usize, dir: &str) {
+    print_message(&format!(r#"wrote {} man pages to "{}""#, count, dir));
+}
+
+pub fn print_labels_updated_message(name: &str, labels: &Labels) {
+    print_message(&get_labels_updated_message_string(name, labels));
+}
+
+fn get_labels_updated_message_string(name: &str, labels: &Labels) -> String {
+    match labels.is_empty() {
+        true => format!(r#"process "{}" has no labels now"#, name),
+        false => format!(
+            r#"process "{}" is labelled {}"#,
+            name,
+            labels::get_labels_string(labels)
+        ),
+    }
+}
+
+pub fn print_signal_sent_message(pid: &str, signal: &str) {
+    print_message(&format!("sent SIG{} to process with pid: {}", signal, pid));
+}
+
+pub fn print_already_running_message(name: &str) {
+    print_message(&get_already_running_message_string(name));
+}
+
+pub fn print_not_running_message(name: &str) {
+    print_message(&get_not_running_message_string(name));
+}
+
+fn get_cgroup_warning_message_string(name: &str, reason: &str) -> String {
+    format!(r#"warning: cgroup for "{}": {}"#, name, reason)
+}
+
+fn get_waiting_for_dependency_message_string(name: &str) -> String {
+    format!(r#"waiting for "{}" to be running..."#, name)
+}
+
+fn get_waiting_for_readiness_message_string(name: &str) -> String {
+    format!(r#"waiting for "{}" to be ready..."#, name)
+}
+
+fn get_health_check_message_string(
+    name: &str,
+    state: &HealthState,
+    failure_threshold: u32,
+) -> String {
+    match state.passed {
+        true => format!(r#"health check for "{}" passed: {}"#, name, state.result),
+        false => format!(
+            r#"health check for "{}" failed ({}/{}): {}"#,
+            name, state.consecutive_failures, failure_threshold, state.result
+        ),
+    }
+}
+
+fn get_already_running_message_string(name: &str) -> String {
+    format!(r#"process "{}" is already running"#, name)
+}
+
+fn get_not_running_message_string(name: &str) -> String {
+    format!(r#"process "{}" is not running"#, name)
+}
+
+fn get_export_skipped_message_string(name: &str) -> String {
+    format!(
+        r#"skipping process "{}": it was hatched before launch specs were tracked"#,
+        name
+    )
+}
+
+fn get_import_action_message_string(action: &ImportAction, dry_run: bool) -> String {
+    let prefix = match dry_run {
+        true => "[dry run] ",
+        false => "",
+    };
+    let message = match action {
+        ImportAction::Hatch { name, spec, .. } => format!(r#"hatch "{}" from {}"#, name, spec.path),
+        ImportAction::Replace { name, spec, .. } => format!(
+            r#"replace running "{}" with a new process from {}"#,
+            name, spec.path
+        ),
+        ImportAction::Skip { name } => format!(r#"skip "{}": already running"#, name),
+    };
+    format!("{}{}", prefix, message)
+}
+
+fn get_plan_action_message_string(action: &PlanAction) -> String {
+    match action {
+        PlanAction::Start { name, spec } => format!(r#"+ start "{}" from {}"#, name, spec.path),
+        PlanAction::Restart { process, .. } => format!(
+            r#"~ restart "{}" (pid {}): launch spec changed"#,
+            process.name, process.pid
+        ),
+        PlanAction::Stop { process } => {
+            format!(r#"- stop "{}" (pid {})"#, process.name, process.pid)
+        }
+    }
+}
+
+fn get_plan_summary_message_string(actions: &[PlanAction]) -> String {
+    if actions.is_empty() {
+        return "no changes, running processes match the manifest".to_string();
+    }
+
+    let count = |matches: fn(&PlanAction) -> bool| actions.iter().filter(|x| matches(x)).count();
+    format!(
+        "plan: {} to start, {} to restart, {} to stop",
+        count(|x| matches!(x, PlanAction::Start { .. })),
+        count(|x| matches!(x, PlanAction::Restart { .. })),
+        count(|x| matches!(x, PlanAction::Stop { .. })),
+    )
 }
 
 fn get_post_hatch_message_string(pid: u32) -> String {
@@ -45,22 +793,212 @@ mod tests {
 
     #[test]
     fn display_output_str_for_empty_vec_should_just_be_header() {
-        let empty_process_list = vec![];
-        let display_string = get_display_output_str_for_processes(empty_process_list);
+        let columns = vec![Column::Name, Column::Pid];
+        let display_string = get_display_output_str_for_processes(&[], &columns);
 
         // since no processes, should only be header
-        let header_string = get_display_header_string();
+        let widths = get_column_widths(&[], &columns, None);
+        let header_string = get_display_header_string(&columns, &widths);
 
         // trim both strings for consistency
         assert_eq!(display_string.trim(), header_string.trim());
     }
 
+    #[test]
+    fn column_widths_should_fit_content_and_narrow_names_to_terminal() {
+        let columns = vec![Column::Name, Column::Pid, Column::Status];
+        let rows = vec![
+            get_test_row("a-rather-long-process-name", "123"),
+            get_test_row("web", "123456789"),
+        ];
+
+        let widths = get_column_widths(&rows, &columns, None);
+        assert_eq!(widths, [26, 9, 7]);
+
+        let widths = get_column_widths(&rows, &columns, Some(30));
+        assert_eq!(widths, [12, 9, 7]);
+        let row_string = get_display_process_row_string(&rows[0], &columns, &widths, false);
+        assert_eq!(row_string, "a-rather-lo… 123       Running");
+
+        // pids and statuses are never cut, even when the names can't shrink any more
+        let widths = get_column_widths(&rows, &columns, Some(10));
+        assert_eq!(widths, [8, 9, 7]);
+    }
+
+    #[test]
+    fn colored_status_should_not_change_the_alignment() {
+        let columns = vec![Column::Status, Column::Pid];
+        let row = get_test_row("web", "123");
+        let widths = get_column_widths(std::slice::from_ref(&row), &columns, None);
+
+        let plain = get_display_process_row_string(&row, &columns, &widths, false);
+        let colored = get_display_process_row_string(&row, &columns, &widths, true);
+
+        assert_eq!(plain, "Running 123");
+        assert!(colored.starts_with(&format!("{}", "Running".with(Color::Green))));
+        assert!(colored.ends_with(" 123"), "{:?}", colored);
+    }
+
+    #[test]
+    fn matched_processes_message_should_list_each_process() {
+        let processes = vec![
+            get_test_process("api-1", "123"),
+            get_test_process("api-2", "456"),
+        ];
+
+        let message = get_matched_processes_message_string(&processes);
+
+        assert!(message.starts_with("2 processes match:\n"), "{}", message);
+        assert!(message.contains("  api-2 (id: , pid: 456)"), "{}", message);
+    }
+
+    #[test]
+    fn labels_updated_message_should_list_labels_or_say_there_are_none() {
+        let mut labels = Labels::new();
+        assert_eq!(
+            get_labels_updated_message_string("web", &labels),
+            r#"process "web" has no labels now"#
+        );
+
+        labels.insert("env".to_string(), "prod".to_string());
+        assert_eq!(
+            get_labels_updated_message_string("web", &labels),
+            r#"process "web" is labelled env=prod"#
+        );
+    }
+
+    #[test]
+    fn cgroup_warning_message_should_name_process_and_reason() {
+        let message = get_cgroup_warning_message_string("web", "memory.max was not set");
+        assert_eq!(
+            message,
+            r#"warning: cgroup for "web": memory.max was not set"#
+        );
+    }
+
+    #[test]
+    fn error_output_should_have_code_and_message_in_every_format() {
+        let json =
+            get_error_output_string("no_such_process", "no such process", OutputFormat::Json);
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["error"]["code"], "no_such_process");
+        assert_eq!(parsed["error"]["message"], "no such process");
+
+        let yaml =
+            get_error_output_string("no_such_process", "no such process", OutputFormat::Yaml);
+        assert!(yaml.contains("code: no_such_process"), "{}", yaml);
+    }
+
+    #[test]
+    fn structured_output_should_serialize_process_records() {
+        let processes = vec![get_test_process("web", "123")];
+
+        let json = get_structured_output_string(&processes, OutputFormat::Json);
+        let parsed: Vec<ProcessInfo> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, processes);
+
+        let yaml = get_structured_output_string(&processes, OutputFormat::Yaml);
+        assert!(yaml.starts_with("- id:"), "{}", yaml);
+        assert!(yaml.contains("pid: '123'"), "{}", yaml);
+    }
+
+    #[test]
+    fn output_format_should_be_parsed_from_name() {
+        for name in crate::subcommands::OUTPUT_FORMAT_NAMES {
+            assert!(OutputFormat::from_name(name).is_some());
+        }
+        assert_eq!(OutputFormat::from_name("xml"), None);
+    }
+
+    #[test]
+    fn color_choice_should_be_parsed_from_name() {
+        for name in crate::subcommands::COLOR_CHOICE_NAMES {
+            assert!(ColorChoice::from_name(name).is_some());
+        }
+        assert_eq!(ColorChoice::from_name("sometimes"), None);
+    }
+
     #[test]
     fn display_header_string_should_be_non_empty() {
-        let msg = get_display_header_string();
+        let widths = get_column_widths(&[], &Column::ALL, None);
+        let msg = get_display_header_string(&Column::ALL, &widths);
         assert!(msg.len() > 0);
     }
 
+    #[test]
+    fn display_output_str_for_namespaces_should_contain_counts() {
+        let namespaces = vec![("default".to_string(), 2), ("web".to_string(), 0)];
+        let display_string = get_display_output_str_for_namespaces(namespaces);
+
+        assert!(display_string.starts_with(&get_namespace_display_header_string()));
+        assert!(display_string.contains("default              2"));
+        assert!(display_string.contains("web                  0"));
+    }
+
+    #[test]
+    fn namespace_title_string_should_contain_namespace() {
+        let title = get_namespace_title_string("web");
+        assert!(title.contains("web"));
+    }
+
+    #[test]
+    fn active_state_display_str_should_contain_path_and_reason() {
+        let source = StateSource::ProjectDir("/srv/app/.eggsecutor".to_string());
+        let display_string = get_active_state_display_str(
+            "/srv/app/.eggsecutor/state",
+            &source,
+            "/srv/app/.eggsecutor/config.toml",
+            "default",
+            Path::new("/srv/app/.eggsecutor/logs"),
+        );
+
+        assert!(display_string.contains("/srv/app/.eggsecutor/state"));
+        assert!(display_string.contains(&get_state_source_reason_string(&source)));
+        assert!(display_string.contains("/srv/app/.eggsecutor/config.toml"));
+        assert!(display_string.contains("/srv/app/.eggsecutor/logs"));
+    }
+
+    #[test]
+    fn state_source_reason_strings_should_mention_source() {
+        assert!(
+            get_state_source_reason_string(&StateSource::EnvVar).contains("EGGSECUTOR_STATE_FILE")
+        );
+        assert!(
+            get_state_source_reason_string(&StateSource::ConfigFile("cfg.toml".to_string()))
+                .contains("cfg.toml")
+        );
+        assert!(
+            get_state_source_reason_string(&StateSource::ProjectDir("/srv/app".to_string()))
+                .contains("/srv/app")
+        );
+    }
+
+    #[test]
+    fn export_skipped_message_should_contain_name() {
+        let message = get_export_skipped_message_string("OLD_PROCESS");
+        assert!(message.contains("OLD_PROCESS"));
+    }
+
+    #[test]
+    fn import_action_message_should_be_prefixed_on_dry_run() {
+        let action = ImportAction::Skip {
+            name: "web".to_string(),
+        };
+
+        let message = get_import_action_message_string(&action, false);
+        let dry_run_message = get_import_action_message_string(&action, true);
+
+        assert!(message.contains("web"));
+        assert!(dry_run_message.starts_with("[dry run]"));
+        assert!(dry_run_message.ends_with(&message));
+    }
+
+    #[test]
+    fn running_state_messages_should_contain_name() {
+        assert!(get_already_running_message_string("web").contains(r#""web""#));
+        assert!(get_not_running_message_string("web").contains(r#""web""#));
+    }
+
     #[test]
     fn pre_hatch_mesage_ok() {
         let filename = "test-filename";
@@ -80,4 +1018,166 @@ mod tests {
         // printing the message should work without error as well
         print_post_hatch_message(pid);
     }
+
+    #[test]
+    fn plan_summary_should_count_each_kind_of_action() {
+        let actions = vec![
+            PlanAction::Stop {
+                process: get_test_process("old", "123"),
+            },
+            PlanAction::Stop {
+                process: get_test_process("older", "456"),
+            },
+        ];
+
+        assert_eq!(
+            get_plan_summary_message_string(&actions),
+            "plan: 0 to start, 0 to restart, 2 to stop"
+        );
+        assert!(get_plan_summary_message_string(&[]).contains("no changes"));
+    }
+
+    #[test]
+    fn plan_display_str_should_end_with_summary() {
+        let actions = vec![PlanAction::Stop {
+            process: get_test_process("old", "123"),
+        }];
+
+        assert_eq!(
+            get_plan_display_str(&actions),
+            "- stop \"old\" (pid 123)\nplan: 0 to start, 0 to restart, 1 to stop"
+        );
+        assert!(get_plan_display_str(&[]).starts_with("no changes"));
+    }
+
+    #[test]
+    fn plan_action_message_should_contain_name_and_pid() {
+        let action = PlanAction::Stop {
+            process: get_test_process("old", "123"),
+        };
+
+        let message = get_plan_action_message_string(&action);
+
+        assert!(message.starts_with('-'));
+        assert!(message.contains("old"));
+        assert!(message.contains("123"));
+    }
+
+    #[test]
+    fn durations_should_show_two_largest_units() {
+        assert_eq!(get_duration_string(42), "42s");
+        assert_eq!(get_duration_string(245), "4m05s");
+        assert_eq!(get_duration_string(7260), "2h01m");
+        assert_eq!(get_duration_string(3 * 86400 + 7200), "3d2h");
+    }
+
+    #[test]
+    fn inspect_display_str_should_show_launch_spec_of_exited_process() {
+        let process: ProcessInfo = serde_json::from_value(serde_json::json!({
+            "name": "web",
+            "pid": "123",
+            "status": "Running",
+            "started_at": 0,
+            "spec": {
+                "path": "/srv/web",
+                "args": ["--title", "my site"],
+                "clear_env": true,
+                "env": {"PORT": "8000"},
+                "limits": [{"resource": "nofile", "soft": 64, "hard": 128}],
+            },
+        }))
+        .unwrap();
+        let inspection = ProcessInspection {
+            process,
+            details: None,
+            cgroup_usage: None,
+        };
+
+        let display_string = get_inspect_display_str(&inspection);
+
+        assert!(display_string.contains("state:         exited"));
+        assert!(display_string.contains(r#"command:       /srv/web --title "my site""#));
+        assert!(display_string
+            .contains("environment:   PORT=8000\n               nothing else inherited"));
+        assert!(display_string.contains("limits:        nofile 64:128"));
+        assert!(display_string.contains("started:       1970-01-01T00:00:00Z"));
+        assert!(display_string.contains("cwd:           -"));
+        assert!(!display_string.contains("exit status"));
+    }
+
+    #[test]
+    fn inspect_display_str_should_show_recorded_exit_status() {
+        let process: ProcessInfo = serde_json::from_value(serde_json::json!({
+            "name": "web",
+            "pid": "123",
+            "status": "Running",
+            "exit_status": "exited with code 3",
+        }))
+        .unwrap();
+        let inspection = ProcessInspection {
+            process,
+            details: None,
+            cgroup_usage: None,
+        };
+
+        let display_string = get_inspect_display_str(&inspection);
+
+        assert!(display_string.contains("exit status:   exited with code 3"));
+    }
+
+    #[test]
+    fn inspect_display_str_should_show_shell_command_from_launch_spec() {
+        let process: ProcessInfo = serde_json::from_value(serde_json::json!({
+            "name": "files",
+            "pid": "123",
+            "status": "Running",
+            "spec": {
+                "path": "/bin/bash",
+                "args": ["-c", "exec python3 -m http.server"],
+                "command": "exec python3 -m http.server",
+            },
+        }))
+        .unwrap();
+        let inspection = ProcessInspection {
+            process,
+            details: None,
+            cgroup_usage: None,
+        };
+
+        let display_string = get_inspect_display_str(&inspection);
+
+        assert!(display_string.contains("command:       exec python3 -m http.server\n"));
+    }
+
+    fn get_test_row(name: &str, pid: &str) -> ProcessRow {
+        ProcessRow {
+            process: get_test_process(name, pid),
+            stats: None,
+            cgroup_usage: None,
+        }
+    }
+
+    fn get_test_process(name: &str, pid: &str) -> ProcessInfo {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "pid": pid,
+            "status": "Running",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn failed_health_check_message_should_show_failure_count() {
+        let state = HealthState {
+            passed: false,
+            result: "TCP port 8000 refused the connection".to_string(),
+            checked_at: 0,
+            consecutive_failures: 2,
+        };
+
+        let message = get_health_check_message_string("web", &state, 3);
+
+        assert!(message.contains("failed (2/3)"));
+        assert!(message.contains(&state.result));
+    }
 }
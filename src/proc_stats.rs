@@ -0,0 +1,378 @@
+use serde::Serialize;
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+// used when sysconf can't tell, it is 100 on practically every Linux system
+const DEFAULT_CLOCK_TICKS_PER_SEC: f64 = 100.0;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ProcessStats {
+    pub uptime_secs: u64,
+    // averaged over the whole lifetime of the process, like `ps` does
+    pub cpu_percent: f64,
+    pub cpu_time_secs: f64,
+    pub rss_bytes: u64,
+    pub threads: u64,
+    // the fds of another user's process can't be read
+    pub open_fds: Option<usize>,
+}
+
+// what `inspect` shows on top of the `list` columns
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ProcessDetails {
+    pub state: String,
+    pub virtual_memory_bytes: u64,
+    pub children: Vec<ChildProcess>,
+    // of the process and everything it started, e.g. "tcp 0.0.0.0:8000"
+    pub listening_sockets: Vec<String>,
+    #[serde(flatten)]
+    pub stats: ProcessStats,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ChildProcess {
+    pub pid: String,
+    pub command: String,
+}
+
+struct ProcEntry {
+    pid: String,
+    parent_pid: String,
+    command: String,
+}
+
+struct StatTimes {
+    cpu_ticks: u64,
+    start_ticks: u64,
+}
+
+// (file under /proc/net, listening state, name shown for it)
+const SOCKET_TABLES: [(&str, &str, &str); 4] = [
+    ("tcp", "0A", "tcp"),
+    ("tcp6", "0A", "tcp6"),
+    ("udp", "07", "udp"),
+    ("udp6", "07", "udp6"),
+];
+
+pub fn read_process_stats(pid: &str) -> Option<ProcessStats> {
+    let proc_dir = Path::new("/proc").join(pid);
+    let stat = fs::read_to_string(proc_dir.join("stat")).ok()?;
+    let status = fs::read_to_string(proc_dir.join("status")).ok()?;
+    let system_uptime = fs::read_to_string("/proc/uptime").ok()?;
+
+    let times = parse_stat_times(&stat)?;
+    let system_uptime_secs = system_uptime
+        .split_whitespace()
+        .next()?
+        .parse::<f64>()
+        .ok()?;
+    let ticks_per_sec = get_clock_ticks_per_sec();
+    let uptime_secs = (system_uptime_secs - times.start_ticks as f64 / ticks_per_sec).max(0.0);
+    let cpu_secs = times.cpu_ticks as f64 / ticks_per_sec;
+
+    Some(ProcessStats {
+        uptime_secs: uptime_secs as u64,
+        cpu_percent: match uptime_secs > 0.0 {
+            true => cpu_secs / uptime_secs * 100.0,
+            false => 0.0,
+        },
+        cpu_time_secs: cpu_secs,
+        // kernel threads have no VmRSS line
+        rss_bytes: parse_status_value(&status, "VmRSS").unwrap_or(0) * 1024,
+        threads: parse_status_value(&status, "Threads").unwrap_or(1),
+        open_fds: fs::read_dir(proc_dir.join("fd")).ok().map(|x| x.count()),
+    })
+}
+
+pub fn read_process_details(pid: &str) -> Option<ProcessDetails> {
+    let stats = read_process_stats(pid)?;
+    let proc_dir = Path::new("/proc").join(pid);
+    let stat = fs::read_to_string(proc_dir.join("stat")).ok()?;
+    let status = fs::read_to_string(proc_dir.join("status")).ok()?;
+    let processes = get_all_processes();
+
+    Some(ProcessDetails {
+        state: get_state_name(parse_stat_field(&stat, 3)?).to_string(),
+        virtual_memory_bytes: parse_status_value(&status, "VmSize").unwrap_or(0) * 1024,
+        children: processes
+            .iter()
+            .filter(|x| x.parent_pid == pid)
+            .map(|x| ChildProcess {
+                pid: x.pid.clone(),
+                command: x.command.clone(),
+            })
+            .collect(),
+        listening_sockets: get_listening_sockets(&get_process_tree(pid, &processes)),
+        stats,
+    })
+}
+
+fn get_state_name(state: &str) -> &str {
+    match state {
+        "R" => "running",
+        "S" => "sleeping",
+        "D" => "waiting on disk",
+        "Z" => "zombie",
+        "T" => "stopped",
+        "t" => "stopped by debugger",
+        "I" => "idle",
+        "X" => "dead",
+        state => state,
+    }
+}
+
+// every process on the system, sorted by pid
+fn get_all_processes() -> Vec<ProcEntry> {
+    let entries = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let mut processes: Vec<ProcEntry> = entries
+        .filter_map(|entry| {
+            let pid = entry.ok()?.file_name().into_string().ok()?;
+            let stat = fs::read_to_string(Path::new("/proc").join(&pid).join("stat")).ok()?;
+            Some(ProcEntry {
+                parent_pid: parse_stat_field(&stat, 4)?.to_string(),
+                command: parse_stat_command(&stat)?.to_string(),
+                pid,
+            })
+        })
+        .collect();
+    processes.sort_by_key(|x| x.pid.parse::<u32>().unwrap_or(0));
+    processes
+}
+
+// the process and all of its descendants
+fn get_process_tree(pid: &str, processes: &[ProcEntry]) -> Vec<String> {
+    let mut tree = vec![pid.to_string()];
+    let mut index = 0;
+    while index < tree.len() {
+        let children = processes.iter().filter(|x| x.parent_pid == tree[index]);
+        tree.extend(children.map(|x| x.pid.clone()).collect::<Vec<String>>());
+        index += 1;
+    }
+    tree
+}
+
+// matches the socket inodes among the processes' fds against the kernel's
+// socket tables, so a server started through `sh -c` still shows its port
+fn get_listening_sockets(pids: &[String]) -> Vec<String> {
+    let socket_inodes: Vec<String> = pids
+        .iter()
+        .filter_map(|pid| fs::read_dir(Path::new("/proc").join(pid).join("fd")).ok())
+        .flatten()
+        .filter_map(|entry| fs::read_link(entry.ok()?.path()).ok())
+        .filter_map(|target| {
+            let target = target.to_string_lossy();
+            Some(
+                target
+                    .strip_prefix("socket:[")?
+                    .strip_suffix(']')?
+                    .to_string(),
+            )
+        })
+        .collect();
+    if socket_inodes.is_empty() {
+        return vec![];
+    }
+
+    let mut sockets = vec![];
+    for &(table, listening_state, protocol) in SOCKET_TABLES.iter() {
+        let contents = match fs::read_to_string(Path::new("/proc/net").join(table)) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+        for (address, inode) in parse_listening_sockets(&contents, listening_state) {
+            let socket = format!("{} {}", protocol, address);
+            if socket_inodes.contains(&inode) && !sockets.contains(&socket) {
+                sockets.push(socket);
+            }
+        }
+    }
+    sockets
+}
+
+// (local address, inode) of every socket in the given state, from a table
+// like /proc/net/tcp: "sl local_address rem_address st ... uid timeout inode"
+fn parse_listening_sockets(table: &str, listening_state: &str) -> Vec<(String, String)> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if *fields.get(3)? != listening_state {
+                return None;
+            }
+            Some((
+                parse_socket_address(fields.get(1)?)?,
+                fields.get(9)?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+// the kernel prints addresses as hex words in host byte order,
+// e.g. "0100007F:1F40" is 127.0.0.1:8000 on little-endian machines
+fn parse_socket_address(address: &str) -> Option<String> {
+    let (host, port) = address.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let mut octets = vec![];
+    for word in host.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(word).ok()?, 16).ok()?;
+        octets.extend_from_slice(&word.to_ne_bytes());
+    }
+    match octets.len() {
+        4 => Some(format!(
+            "{}:{}",
+            Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]),
+            port
+        )),
+        16 => {
+            let mut ipv6 = [0u8; 16];
+            ipv6.copy_from_slice(&octets);
+            Some(format!("[{}]:{}", Ipv6Addr::from(ipv6), port))
+        }
+        _ => None,
+    }
+}
+
+fn get_clock_ticks_per_sec() -> f64 {
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as f64,
+        _ => DEFAULT_CLOCK_TICKS_PER_SEC,
+    }
+}
+
+// the command name in parentheses may itself contain spaces and parentheses,
+// so fields are counted from the last closing one, where field 3 starts
+fn parse_stat_field(stat: &str, number: usize) -> Option<&str> {
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(number.checked_sub(3)?)
+}
+
+fn parse_stat_command(stat: &str) -> Option<&str> {
+    let (_, command) = stat.split_once('(')?;
+    let (command, _) = command.rsplit_once(')')?;
+    Some(command)
+}
+
+fn parse_stat_times(stat: &str) -> Option<StatTimes> {
+    let get_field = |number: usize| parse_stat_field(stat, number)?.parse::<u64>().ok();
+
+    Some(StatTimes {
+        cpu_ticks: get_field(14)? + get_field(15)?,
+        start_ticks: get_field(22)?,
+    })
+}
+
+// e.g. "VmRSS:	    5120 kB" gives 5120
+fn parse_status_value(status: &str, key: &str) -> Option<u64> {
+    status.lines().find_map(|line| {
+        let value = line.strip_prefix(key)?.strip_prefix(':')?;
+        value.split_whitespace().next()?.parse().ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn stat_times_should_be_parsed_after_command_name() {
+        let stat = "4242 (my (weird) cmd) S 1 4242 4242 0 -1 4194560 120 0 0 0 \
+                    250 50 0 0 20 0 3 0 98765 1234567 321 18446744073709551615";
+
+        let times = parse_stat_times(stat).unwrap();
+
+        assert_eq!(times.cpu_ticks, 300);
+        assert_eq!(times.start_ticks, 98765);
+        assert_eq!(parse_stat_command(stat), Some("my (weird) cmd"));
+        assert_eq!(parse_stat_field(stat, 3), Some("S"));
+        assert_eq!(parse_stat_field(stat, 4), Some("1"));
+        assert!(parse_stat_times("4242 (cmd) S 1").is_none());
+    }
+
+    #[test]
+    fn listening_sockets_should_be_parsed_from_socket_table() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F40 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 51234 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:9C40 0100007F:1F40 01 00000000:00000000 00:00000000 00000000  1000        0 51240 1 0000000000000000 20 4 30 10 -1";
+
+        let sockets = parse_listening_sockets(table, "0A");
+
+        if cfg!(target_endian = "little") {
+            assert_eq!(
+                sockets,
+                [("127.0.0.1:8000".to_string(), "51234".to_string())]
+            );
+        }
+        assert_eq!(sockets.len(), 1);
+    }
+
+    #[test]
+    fn ipv6_socket_addresses_should_be_bracketed() {
+        let address = parse_socket_address("00000000000000000000000000000000:0050").unwrap();
+        assert_eq!(address, "[::]:80");
+        assert!(parse_socket_address("0100007F").is_none());
+    }
+
+    #[test]
+    fn status_values_should_be_parsed_by_key() {
+        let status = "Name:\tsleep\nThreads:\t4\nVmRSS:\t    5120 kB\n";
+
+        assert_eq!(parse_status_value(status, "VmRSS"), Some(5120));
+        assert_eq!(parse_status_value(status, "Threads"), Some(4));
+        assert_eq!(parse_status_value(status, "VmSwap"), None);
+    }
+
+    #[test]
+    fn own_process_stats_should_be_readable() {
+        let stats = read_process_stats(&process::id().to_string()).unwrap();
+
+        assert!(stats.rss_bytes > 0);
+        assert!(stats.threads >= 1);
+        assert!(stats.open_fds.unwrap() > 0);
+        assert!(read_process_stats("not-a-pid").is_none());
+    }
+
+    #[test]
+    fn process_tree_should_include_all_descendants() {
+        let get_entry = |pid: &str, parent_pid: &str| ProcEntry {
+            pid: pid.to_string(),
+            parent_pid: parent_pid.to_string(),
+            command: "sh".to_string(),
+        };
+        let processes = vec![
+            get_entry("1", "0"),
+            get_entry("10", "1"),
+            get_entry("11", "10"),
+            get_entry("12", "11"),
+            get_entry("20", "1"),
+        ];
+
+        assert_eq!(get_process_tree("10", &processes), ["10", "11", "12"]);
+    }
+
+    #[test]
+    fn child_processes_and_listening_sockets_should_be_found() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .spawn()
+            .unwrap();
+
+        let details = read_process_details(&process::id().to_string()).unwrap();
+        child.kill().and_then(|_| child.wait()).unwrap();
+
+        assert!(details
+            .children
+            .iter()
+            .any(|x| x.pid == child.id().to_string()));
+        assert!(details
+            .listening_sockets
+            .contains(&format!("tcp 127.0.0.1:{}", port)));
+    }
+}
@@ -0,0 +1,232 @@
+use super::manifest::ManifestProcesses;
+use super::LaunchSpec;
+use std::fs;
+
+// every process type gets one instance unless the formation says otherwise
+const DEFAULT_INSTANCE_COUNT: usize = 1;
+const FORMATION_ALL_KEY: &str = "all";
+const PROCFILE_SHELL: &str = "/bin/sh";
+
+pub type ProcfileEntries = Vec<(String, String)>;
+
+pub fn load_procfile(path: &str) -> Result<(ProcfileEntries, String), String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let procfile_dir = fs::canonicalize(path)
+        .map_err(|err| err.to_string())?
+        .parent()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    Ok((parse_procfile(&contents)?, procfile_dir))
+}
+
+// turns each `name: command` entry into `name.1`, `name.2`... instances run through `sh -c`
+pub fn get_instances(
+    entries: ProcfileEntries,
+    formation: &[(String, usize)],
+    procfile_dir: &str,
+) -> Result<ManifestProcesses, String> {
+    if let Some((unknown, _)) = formation
+        .iter()
+        .find(|(name, _)| name != FORMATION_ALL_KEY && !entries.iter().any(|(x, _)| x == name))
+    {
+        return Err(format!(r#"no process type "{}" in Procfile"#, unknown));
+    }
+
+    let mut instances = vec![];
+    for (name, command) in entries {
+        for instance in 1..=get_instance_count(&name, formation) {
+            let spec = LaunchSpec {
+                path: PROCFILE_SHELL.to_string(),
+                // exec'ing replaces the shell, so stop signals reach the command itself
+                args: vec!["-c".to_string(), format!("exec {}", command)],
+                cwd: Some(procfile_dir.to_string()),
+                ..LaunchSpec::default()
+            };
+            instances.push((format!("{}.{}", name, instance), spec));
+        }
+    }
+    Ok(instances)
+}
+
+pub fn parse_formation(formation: &str) -> Result<Vec<(String, usize)>, String> {
+    formation
+        .split(',')
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(|pair| {
+            let (name, count) = pair
+                .split_once('=')
+                .ok_or_else(|| format!(r#"invalid formation "{}", expected NAME=COUNT"#, pair))?;
+            let count = count
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!(r#"invalid instance count in formation "{}""#, pair))?;
+            Ok((name.trim().to_string(), count))
+        })
+        .collect()
+}
+
+pub fn is_instance_of(process_name: &str, entry_name: &str) -> bool {
+    match process_name
+        .strip_prefix(entry_name)
+        .and_then(|x| x.strip_prefix('.'))
+    {
+        Some(instance) => !instance.is_empty() && instance.chars().all(|x| x.is_ascii_digit()),
+        None => false,
+    }
+}
+
+fn get_instance_count(name: &str, formation: &[(String, usize)]) -> usize {
+    let find_count = |key: &str| {
+        formation
+            .iter()
+            .find(|(x, _)| x == key)
+            .map(|(_, count)| *count)
+    };
+
+    find_count(name)
+        .or_else(|| find_count(FORMATION_ALL_KEY))
+        .unwrap_or(DEFAULT_INSTANCE_COUNT)
+}
+
+fn parse_procfile(contents: &str) -> Result<ProcfileEntries, String> {
+    let mut entries: ProcfileEntries = vec![];
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (name, command) = line
+            .split_once(':')
+            .map(|(name, command)| (name.trim(), command.trim()))
+            .filter(|(name, command)| is_valid_process_type(name) && !command.is_empty())
+            .ok_or_else(|| {
+                format!(
+                    r#"invalid Procfile line {}, expected "name: command""#,
+                    line_number + 1
+                )
+            })?;
+
+        if entries.iter().any(|(x, _)| x == name) {
+            return Err(format!(r#"duplicate process type "{}" in Procfile"#, name));
+        }
+        entries.push((name.to_string(), command.to_string()));
+    }
+    Ok(entries)
+}
+
+fn is_valid_process_type(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_PROCFILE: &str = "
+        # the web server
+        web: python -m http.server $PORT
+
+        worker: bundle exec sidekiq -c 5
+    ";
+
+    #[test]
+    fn procfile_should_parse_entries_in_order() {
+        let entries = parse_procfile(TEST_PROCFILE).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                ("web".to_string(), "python -m http.server $PORT".to_string()),
+                ("worker".to_string(), "bundle exec sidekiq -c 5".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn command_containing_colons_should_be_kept_whole() {
+        let entries = parse_procfile("web: curl http://localhost:8000").unwrap();
+        assert_eq!(entries[0].1, "curl http://localhost:8000");
+    }
+
+    #[test]
+    fn invalid_procfile_lines_should_be_err() {
+        for contents in ["web python", ": python", "web:", "bad name: python"] {
+            assert!(
+                parse_procfile(contents).is_err(),
+                "{} should be err",
+                contents
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_process_types_should_be_err() {
+        assert!(parse_procfile("web: a\nweb: b").is_err());
+    }
+
+    #[test]
+    fn formation_should_parse_counts() {
+        let formation = parse_formation("web=2, worker=0").unwrap();
+        assert_eq!(
+            formation,
+            vec![("web".to_string(), 2), ("worker".to_string(), 0)]
+        );
+    }
+
+    #[test]
+    fn invalid_formation_should_be_err() {
+        assert!(parse_formation("web").is_err());
+        assert!(parse_formation("web=two").is_err());
+    }
+
+    #[test]
+    fn instances_should_follow_formation() {
+        let entries = parse_procfile(TEST_PROCFILE).unwrap();
+        let formation = parse_formation("web=2,worker=0").unwrap();
+
+        let instances = get_instances(entries, &formation, "/srv/app").unwrap();
+
+        let names: Vec<&str> = instances.iter().map(|(x, _)| x.as_str()).collect();
+        assert_eq!(names, vec!["web.1", "web.2"]);
+
+        let spec = &instances[0].1;
+        assert_eq!(spec.path, PROCFILE_SHELL);
+        assert_eq!(spec.args, vec!["-c", "exec python -m http.server $PORT"]);
+        assert_eq!(spec.cwd.as_deref(), Some("/srv/app"));
+    }
+
+    #[test]
+    fn formation_all_key_should_apply_to_unlisted_types() {
+        let entries = parse_procfile(TEST_PROCFILE).unwrap();
+        let formation = parse_formation("all=2,web=1").unwrap();
+
+        let instances = get_instances(entries, &formation, "/srv/app").unwrap();
+
+        let names: Vec<&str> = instances.iter().map(|(x, _)| x.as_str()).collect();
+        assert_eq!(names, vec!["web.1", "worker.1", "worker.2"]);
+    }
+
+    #[test]
+    fn formation_with_unknown_type_should_be_err() {
+        let entries = parse_procfile(TEST_PROCFILE).unwrap();
+        let formation = parse_formation("clock=1").unwrap();
+
+        assert!(get_instances(entries, &formation, "/srv/app").is_err());
+    }
+
+    #[test]
+    fn instance_names_should_match_their_type() {
+        assert!(is_instance_of("web.1", "web"));
+        assert!(is_instance_of("web.12", "web"));
+        assert!(!is_instance_of("web", "web"));
+        assert!(!is_instance_of("web.", "web"));
+        assert!(!is_instance_of("webhook.1", "web"));
+        assert!(!is_instance_of("web.one", "web"));
+    }
+}
@@ -0,0 +1,94 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+const PROJECT_DIR_NAME: &str = ".eggsecutor";
+
+pub fn find_project_dir() -> Option<PathBuf> {
+    let current_dir = env::current_dir().ok()?;
+    find_project_dir_from(&current_dir)
+}
+
+pub fn get_project_state_file_path(project_dir: &Path) -> PathBuf {
+    project_dir.join("state")
+}
+
+pub fn get_project_config_file_path(project_dir: &Path) -> PathBuf {
+    project_dir.join("config.toml")
+}
+
+pub fn get_project_logs_dir(project_dir: &Path) -> PathBuf {
+    project_dir.join("logs")
+}
+
+// walks up from the starting directory the same way git looks for `.git/`
+fn find_project_dir_from(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_DIR_NAME))
+        .find(|candidate| candidate.is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestDir;
+    use std::fs;
+
+    #[test]
+    fn project_dir_should_be_found_in_start_dir() {
+        let test_dir = TestDir::new();
+        let project_dir = test_dir.path.join(PROJECT_DIR_NAME);
+        fs::create_dir_all(&project_dir).unwrap();
+
+        assert_eq!(find_project_dir_from(&test_dir.path), Some(project_dir));
+    }
+
+    #[test]
+    fn project_dir_should_be_found_in_ancestor_dir() {
+        let test_dir = TestDir::new();
+        let project_dir = test_dir.path.join(PROJECT_DIR_NAME);
+        let nested_dir = test_dir.path.join("src").join("bin");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::create_dir_all(&nested_dir).unwrap();
+
+        assert_eq!(find_project_dir_from(&nested_dir), Some(project_dir));
+    }
+
+    #[test]
+    fn closest_project_dir_should_win() {
+        let test_dir = TestDir::new();
+        let nested_dir = test_dir.path.join("nested");
+        let nested_project_dir = nested_dir.join(PROJECT_DIR_NAME);
+        fs::create_dir_all(test_dir.path.join(PROJECT_DIR_NAME)).unwrap();
+        fs::create_dir_all(&nested_project_dir).unwrap();
+
+        assert_eq!(find_project_dir_from(&nested_dir), Some(nested_project_dir));
+    }
+
+    #[test]
+    fn project_file_that_is_not_a_dir_should_be_ignored() {
+        let test_dir = TestDir::new();
+        fs::create_dir_all(&test_dir.path).unwrap();
+        fs::write(test_dir.path.join(PROJECT_DIR_NAME), "").unwrap();
+
+        // there may be a real project dir further up, but never this file
+        assert_ne!(
+            find_project_dir_from(&test_dir.path),
+            Some(test_dir.path.join(PROJECT_DIR_NAME))
+        );
+    }
+
+    #[test]
+    fn project_paths_should_be_inside_project_dir() {
+        let project_dir = Path::new("/srv/app/.eggsecutor");
+        assert_eq!(
+            get_project_state_file_path(project_dir),
+            project_dir.join("state")
+        );
+        assert_eq!(
+            get_project_config_file_path(project_dir),
+            project_dir.join("config.toml")
+        );
+        assert_eq!(get_project_logs_dir(project_dir), project_dir.join("logs"));
+    }
+}
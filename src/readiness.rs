@@ -0,0 +1,233 @@
+use regex::Regex;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// how long a single TCP or HTTP probe may take before counting as a failure
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+// used when --wait-ready is given without any specific check
+const DEFAULT_ALIVE_DURATION: Duration = Duration::from_millis(500);
+
+pub struct Readiness {
+    pub checks: Vec<ReadinessCheck>,
+    pub timeout: Duration,
+}
+
+#[derive(Debug)]
+pub enum ReadinessCheck {
+    Alive(Duration),
+    Tcp(u16),
+    Http { port: u16, path: String },
+    File(PathBuf),
+    LogLine(Regex),
+}
+
+impl ReadinessCheck {
+    pub fn default_checks() -> Vec<Self> {
+        vec![ReadinessCheck::Alive(DEFAULT_ALIVE_DURATION)]
+    }
+
+    pub fn parse_alive(millis: &str) -> Result<Self, String> {
+        millis
+            .parse::<u64>()
+            .map(|x| ReadinessCheck::Alive(Duration::from_millis(x)))
+            .map_err(|_| format!(r#"invalid duration "{}", expected milliseconds"#, millis))
+    }
+
+    pub fn parse_tcp(port: &str) -> Result<Self, String> {
+        parse_port(port).map(ReadinessCheck::Tcp)
+    }
+
+    pub fn parse_http(target: &str) -> Result<Self, String> {
+        let (port, path) = parse_http_target(target)?;
+        Ok(ReadinessCheck::Http { port, path })
+    }
+
+    pub fn parse_file(path: &str) -> Result<Self, String> {
+        Ok(ReadinessCheck::File(PathBuf::from(path)))
+    }
+
+    pub fn parse_log_line(pattern: &str) -> Result<Self, String> {
+        Regex::new(pattern)
+            .map(ReadinessCheck::LogLine)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ReadinessCheck::Alive(duration) => {
+                format!("process alive for {}ms", duration.as_millis())
+            }
+            ReadinessCheck::Tcp(port) => format!("TCP port {} accepting connections", port),
+            ReadinessCheck::Http { port, path } => {
+                format!("HTTP GET localhost:{}{} returning 2xx", port, path)
+            }
+            ReadinessCheck::File(path) => format!(r#"file "{}" existing"#, path.display()),
+            ReadinessCheck::LogLine(regex) => format!(r#"log line matching "{}""#, regex),
+        }
+    }
+
+    // `log_offset` is where the log ended before the process started, since
+    // log files are appended to across runs
+    pub fn is_passing(&self, started_at: Instant, log_path: &Path, log_offset: u64) -> bool {
+        match self {
+            ReadinessCheck::Alive(duration) => started_at.elapsed() >= *duration,
+            ReadinessCheck::Tcp(port) => connect_to_local_port(*port).is_some(),
+            ReadinessCheck::Http { port, path } => is_http_get_successful(*port, path),
+            ReadinessCheck::File(path) => path.exists(),
+            ReadinessCheck::LogLine(regex) => read_log_from_offset(log_path, log_offset)
+                .map(|log| regex.is_match(&log))
+                .unwrap_or(false),
+        }
+    }
+}
+
+pub fn parse_port(port: &str) -> Result<u16, String> {
+    port.parse::<u16>()
+        .map_err(|_| format!(r#"invalid port "{}""#, port))
+}
+
+// accepts "8000" or "8000/healthz"
+pub fn parse_http_target(target: &str) -> Result<(u16, String), String> {
+    let (port, path) = match target.split_once('/') {
+        Some((port, path)) => (port, format!("/{}", path)),
+        None => (target, "/".to_string()),
+    };
+    Ok((parse_port(port)?, path))
+}
+
+pub fn connect_to_local_port(port: u16) -> Option<TcpStream> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    TcpStream::connect_timeout(&address, PROBE_TIMEOUT).ok()
+}
+
+pub fn is_http_get_successful(port: u16, path: &str) -> bool {
+    let mut stream = match connect_to_local_port(port) {
+        Some(stream) => stream,
+        None => return false,
+    };
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: localhost:{}\r\nConnection: close\r\n\r\n",
+        path, port
+    );
+    let mut response = vec![];
+    let sent = stream.set_read_timeout(Some(PROBE_TIMEOUT)).is_ok()
+        && stream.write_all(request.as_bytes()).is_ok();
+
+    // the status line is all we need, so a read that times out later is fine
+    let _result = sent && stream.read_to_end(&mut response).is_ok();
+    is_success_status_line(&String::from_utf8_lossy(&response))
+}
+
+fn is_success_status_line(response: &str) -> bool {
+    response
+        .lines()
+        .next()
+        .and_then(|status_line| status_line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .map(|code| (200..300).contains(&code))
+        .unwrap_or(false)
+}
+
+fn read_log_from_offset(log_path: &Path, log_offset: u64) -> Option<String> {
+    let mut file = File::open(log_path).ok()?;
+    file.seek(SeekFrom::Start(log_offset)).ok()?;
+    let mut contents = vec![];
+    file.read_to_end(&mut contents).ok()?;
+    Some(String::from_utf8_lossy(&contents).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::net::TcpListener;
+    use std::thread;
+    use uuid::Uuid;
+
+    #[test]
+    fn http_target_should_parse_port_and_path() {
+        let check = ReadinessCheck::parse_http("8000/health/live").unwrap();
+        assert!(matches!(
+            check,
+            ReadinessCheck::Http { port: 8000, ref path } if path == "/health/live"
+        ));
+
+        let check = ReadinessCheck::parse_http("8000").unwrap();
+        assert!(matches!(check, ReadinessCheck::Http { ref path, .. } if path == "/"));
+    }
+
+    #[test]
+    fn invalid_check_values_should_be_err() {
+        assert!(ReadinessCheck::parse_tcp("http").is_err());
+        assert!(ReadinessCheck::parse_http("99999/health").is_err());
+        assert!(ReadinessCheck::parse_alive("1s").is_err());
+        assert!(ReadinessCheck::parse_log_line("(unclosed").is_err());
+    }
+
+    #[test]
+    fn only_2xx_status_lines_should_be_successful() {
+        assert!(is_success_status_line("HTTP/1.1 200 OK\r\n"));
+        assert!(is_success_status_line("HTTP/1.0 204 No Content\r\n"));
+        assert!(!is_success_status_line(
+            "HTTP/1.1 503 Service Unavailable\r\n"
+        ));
+        assert!(!is_success_status_line(""));
+    }
+
+    #[test]
+    fn alive_check_should_pass_after_duration() {
+        let check = ReadinessCheck::Alive(Duration::from_millis(50));
+        let started_at = Instant::now();
+
+        assert!(!check.is_passing(started_at, Path::new(""), 0));
+        thread::sleep(Duration::from_millis(60));
+        assert!(check.is_passing(started_at, Path::new(""), 0));
+    }
+
+    #[test]
+    fn tcp_check_should_pass_once_port_is_listening() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let check = ReadinessCheck::Tcp(port);
+
+        assert!(check.is_passing(Instant::now(), Path::new(""), 0));
+    }
+
+    #[test]
+    fn http_check_should_pass_on_2xx_response() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 512];
+            let _read = stream.read(&mut request).unwrap();
+            stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
+        });
+
+        let check = ReadinessCheck::parse_http(&format!("{}/health", port)).unwrap();
+
+        assert!(check.is_passing(Instant::now(), Path::new(""), 0));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn log_line_check_should_ignore_output_before_offset() {
+        let log_path = env::temp_dir().join(format!("{}.log", Uuid::new_v4().to_simple()));
+        fs::write(&log_path, "listening on 8000\n").unwrap();
+        let log_offset = fs::metadata(&log_path).unwrap().len();
+        let check = ReadinessCheck::parse_log_line("listening on \\d+").unwrap();
+
+        let passing_before = check.is_passing(Instant::now(), &log_path, log_offset);
+        fs::write(&log_path, "listening on 8000\nlistening on 9000\n").unwrap();
+        let passing_after = check.is_passing(Instant::now(), &log_path, log_offset);
+        fs::remove_file(&log_path).unwrap();
+
+        assert!(!passing_before);
+        assert!(passing_after);
+    }
+}
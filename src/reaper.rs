@@ -0,0 +1,236 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, ExitStatus};
+
+// a process hatched through a waiter, which reaps it and reports how it ended
+pub struct ReapedChild {
+    pid: u32,
+    status_reader: BufReader<File>,
+    // the waiter holds off calling `on_exit` until this is closed
+    _release_writer: File,
+}
+
+impl ReapedChild {
+    pub fn id(&self) -> u32 {
+        self.pid
+    }
+
+    // None while the process is still running
+    pub fn try_wait(&mut self) -> Option<String> {
+        if self.status_reader.buffer().is_empty() {
+            let mut poll_fd = libc::pollfd {
+                fd: self.status_reader.get_ref().as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if unsafe { libc::poll(&mut poll_fd, 1, 0) } <= 0 {
+                return None;
+            }
+        }
+        let mut line = String::new();
+        self.status_reader.read_line(&mut line).ok()?;
+        line.trim_end()
+            .strip_prefix("exited ")
+            .map(|x| x.to_string())
+    }
+
+    pub fn kill(&self) -> io::Result<()> {
+        match unsafe { libc::kill(self.pid as libc::pid_t, libc::SIGKILL) } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+}
+
+// the waiter is orphaned straight away, so it is never left as a zombie of ours even
+// when we keep running, e.g. restarts from `top`; `on_exit` runs in the waiter once the
+// process has exited and the returned ReapedChild has been dropped, or we have exited
+pub fn spawn<F>(command: &mut Command, on_exit: F) -> io::Result<ReapedChild>
+where
+    F: FnOnce(u32, &str),
+{
+    let (status_reader, status_writer) = create_pipe()?;
+    let (release_reader, release_writer) = create_pipe()?;
+
+    match unsafe { libc::fork() } {
+        -1 => return Err(io::Error::last_os_error()),
+        0 => {
+            drop(status_reader);
+            drop(release_writer);
+            if unsafe { libc::fork() } == 0 {
+                run_waiter(command, status_writer, release_reader, on_exit);
+            }
+            unsafe { libc::_exit(0) };
+        }
+        intermediate_pid => {
+            let mut wait_status = 0;
+            unsafe { libc::waitpid(intermediate_pid, &mut wait_status, 0) };
+        }
+    }
+    drop(status_writer);
+    drop(release_reader);
+
+    let mut status_reader = BufReader::new(status_reader);
+    let mut line = String::new();
+    status_reader.read_line(&mut line)?;
+    let pid = parse_pid_line(line.trim_end())?;
+
+    Ok(ReapedChild {
+        pid,
+        status_reader,
+        _release_writer: release_writer,
+    })
+}
+
+pub fn describe_exit_status(status: ExitStatus) -> String {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => format!("exited with code {}", code),
+        (None, Some(signal)) => format!("killed by signal {}", signal),
+        (None, None) => status.to_string(),
+    }
+}
+
+fn run_waiter<F>(
+    command: &mut Command,
+    mut status_writer: File,
+    mut release_reader: File,
+    on_exit: F,
+) -> !
+where
+    F: FnOnce(u32, &str),
+{
+    // holding on to our stdout would keep e.g. `eggsecutor hatch ... | jq` from finishing
+    redirect_output_to_null();
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            let _result = writeln!(status_writer, "{}", get_spawn_error_line(&err));
+            unsafe { libc::_exit(1) };
+        }
+    };
+    let pid = child.id();
+    let _result = writeln!(status_writer, "pid {}", pid);
+
+    // the process inherits signal dispositions, so they are only ignored after spawning it
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_IGN);
+        libc::signal(libc::SIGHUP, libc::SIG_IGN);
+    }
+    let exit_status = match child.wait() {
+        Ok(status) => describe_exit_status(status),
+        Err(err) => err.to_string(),
+    };
+    let _result = writeln!(status_writer, "exited {}", exit_status);
+    drop(status_writer);
+
+    // the process may not be in the state file yet
+    let _result = release_reader.read_to_end(&mut vec![]);
+    on_exit(pid, &exit_status);
+    unsafe { libc::_exit(0) };
+}
+
+fn get_spawn_error_line(err: &io::Error) -> String {
+    match err.raw_os_error() {
+        Some(code) => format!("errno {}", code),
+        None => format!("error {}", err),
+    }
+}
+
+fn parse_pid_line(line: &str) -> io::Result<u32> {
+    if let Some(pid) = line.strip_prefix("pid ") {
+        return pid.parse().map_err(io::Error::other);
+    }
+    if let Some(code) = line.strip_prefix("errno ").and_then(|x| x.parse().ok()) {
+        return Err(io::Error::from_raw_os_error(code));
+    }
+    let reason = line
+        .strip_prefix("error ")
+        .unwrap_or("the waiter exited before starting the process");
+    Err(io::Error::other(reason.to_string()))
+}
+
+fn redirect_output_to_null() {
+    let null_path = b"/dev/null\0";
+    let fd = unsafe { libc::open(null_path.as_ptr() as *const libc::c_char, libc::O_WRONLY) };
+    if fd >= 0 {
+        unsafe {
+            libc::dup2(fd, libc::STDOUT_FILENO);
+            libc::dup2(fd, libc::STDERR_FILENO);
+            libc::close(fd);
+        }
+    }
+}
+
+// both ends are closed on exec, so the process never holds on to them
+fn create_pipe() -> io::Result<(File, File)> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pid_line_should_parse_into_pid() {
+        assert_eq!(parse_pid_line("pid 1234").unwrap(), 1234);
+    }
+
+    #[test]
+    fn errno_line_should_keep_error_kind() {
+        let err = parse_pid_line(&get_spawn_error_line(&io::Error::from_raw_os_error(
+            libc::ENOENT,
+        )))
+        .unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn empty_line_should_be_err() {
+        assert!(parse_pid_line("").is_err());
+    }
+
+    #[test]
+    fn exit_code_should_be_described() {
+        let status = ExitStatus::from_raw(3 << 8);
+
+        assert_eq!(describe_exit_status(status), "exited with code 3");
+    }
+
+    #[test]
+    fn signal_should_be_described() {
+        let status = ExitStatus::from_raw(libc::SIGKILL);
+
+        assert_eq!(describe_exit_status(status), "killed by signal 9");
+    }
+
+    #[test]
+    fn reaped_child_should_report_exit_status() {
+        let mut child = spawn(Command::new("sh").args(["-c", "exit 3"]), |_, _| {}).unwrap();
+
+        let mut exit_status = None;
+        for _ in 0..50 {
+            exit_status = child.try_wait();
+            if exit_status.is_some() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+
+        assert_eq!(exit_status.as_deref(), Some("exited with code 3"));
+    }
+
+    #[test]
+    fn missing_binary_should_be_spawn_err() {
+        let result = spawn(&mut Command::new("/nonexistent/eggsecutor-test"), |_, _| {});
+
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::NotFound);
+    }
+}
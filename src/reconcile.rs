@@ -0,0 +1,162 @@
+use super::manifest::ManifestProcesses;
+use super::{LaunchSpec, ProcessInfo};
+
+// plans are a handful of actions, so boxing the larger variants isn't worth it
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, PartialEq)]
+pub enum PlanAction {
+    Start {
+        name: String,
+        spec: LaunchSpec,
+    },
+    Restart {
+        process: ProcessInfo,
+        spec: LaunchSpec,
+    },
+    Stop {
+        process: ProcessInfo,
+    },
+}
+
+// the manifest is the whole desired state: running processes it doesn't declare
+// are stopped, and so are extra instances sharing a declared name
+pub fn plan_changes(desired: ManifestProcesses, running: &[ProcessInfo]) -> Vec<PlanAction> {
+    let mut stops = vec![];
+    let mut restarts = vec![];
+    let mut starts = vec![];
+    let mut matched_names: Vec<&str> = vec![];
+
+    for process in running {
+        let declared = desired.iter().find(|(name, _)| *name == process.name);
+        match declared {
+            Some((name, spec)) if !matched_names.contains(&name.as_str()) => {
+                matched_names.push(name);
+                if *spec != process.spec {
+                    restarts.push(PlanAction::Restart {
+                        process: process.clone(),
+                        spec: spec.clone(),
+                    });
+                }
+            }
+            _ => stops.push(PlanAction::Stop {
+                process: process.clone(),
+            }),
+        }
+    }
+
+    for (name, spec) in desired.iter() {
+        if !matched_names.contains(&name.as_str()) {
+            starts.push(PlanAction::Start {
+                name: name.clone(),
+                spec: spec.clone(),
+            });
+        }
+    }
+
+    stops.into_iter().chain(restarts).chain(starts).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProcessStatus;
+
+    #[test]
+    fn nothing_running_should_start_everything() {
+        let desired = vec![get_desired("web", "/bin/web")];
+
+        let actions = plan_changes(desired, &[]);
+
+        assert_eq!(
+            actions,
+            vec![PlanAction::Start {
+                name: "web".to_string(),
+                spec: get_spec("/bin/web"),
+            }]
+        );
+    }
+
+    #[test]
+    fn matching_spec_should_need_no_changes() {
+        let desired = vec![get_desired("web", "/bin/web")];
+        let running = vec![get_running("web", "aaaa", "/bin/web")];
+
+        assert!(plan_changes(desired, &running).is_empty());
+    }
+
+    #[test]
+    fn changed_spec_should_restart() {
+        let desired = vec![get_desired("web", "/bin/web-v2")];
+        let running = vec![get_running("web", "aaaa", "/bin/web")];
+
+        let actions = plan_changes(desired, &running);
+
+        assert_eq!(
+            actions,
+            vec![PlanAction::Restart {
+                process: running[0].clone(),
+                spec: get_spec("/bin/web-v2"),
+            }]
+        );
+    }
+
+    #[test]
+    fn undeclared_and_duplicate_processes_should_stop_first() {
+        let desired = vec![
+            get_desired("web", "/bin/web"),
+            get_desired("worker", "/bin/worker"),
+        ];
+        let running = vec![
+            get_running("web", "aaaa", "/bin/web"),
+            get_running("web", "bbbb", "/bin/web"),
+            get_running("old", "cccc", "/bin/old"),
+        ];
+
+        let actions = plan_changes(desired, &running);
+
+        assert_eq!(
+            actions,
+            vec![
+                PlanAction::Stop {
+                    process: running[1].clone(),
+                },
+                PlanAction::Stop {
+                    process: running[2].clone(),
+                },
+                PlanAction::Start {
+                    name: "worker".to_string(),
+                    spec: get_spec("/bin/worker"),
+                },
+            ]
+        );
+    }
+
+    fn get_spec(path: &str) -> LaunchSpec {
+        LaunchSpec {
+            path: path.to_string(),
+            ..LaunchSpec::default()
+        }
+    }
+
+    fn get_desired(name: &str, path: &str) -> (String, LaunchSpec) {
+        (name.to_string(), get_spec(path))
+    }
+
+    fn get_running(name: &str, id: &str, path: &str) -> ProcessInfo {
+        ProcessInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            pid: "1".to_string(),
+            status: ProcessStatus::Running,
+            log_file: None,
+            spec: get_spec(path),
+            after: vec![],
+            health: None,
+            restart_count: 0,
+            cgroup: None,
+            started_at: None,
+            labels: Default::default(),
+            exit_status: None,
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use super::labels::LabelSelector;
+use super::proc_stats;
+use super::{ProcessInfo, ProcessStatus};
+use regex::Regex;
+use std::time::Duration;
+
+// which of the tracked processes a bulk operation applies to, a process has to
+// match everything that was given
+#[derive(Debug, Default)]
+pub struct Selector {
+    // a name, id or pid, or a glob of names like "api-*"
+    pub identifier: Option<String>,
+    pub statuses: Vec<ProcessStatus>,
+    pub older_than: Option<Duration>,
+    pub labels: Vec<LabelSelector>,
+}
+
+impl Selector {
+    // a plain identifier is a lookup of one process rather than a filter
+    pub fn is_single_identifier(&self) -> bool {
+        self.identifier.as_deref().is_some_and(|x| !is_glob(x))
+            && self.statuses.is_empty()
+            && self.older_than.is_none()
+            && self.labels.is_empty()
+    }
+
+    pub fn select(&self, processes: Vec<ProcessInfo>, now: u64) -> Vec<ProcessInfo> {
+        let processes = match &self.identifier {
+            Some(identifier) => get_identified_processes(identifier, processes),
+            None => processes,
+        };
+        processes
+            .into_iter()
+            .filter(|x| self.statuses.is_empty() || self.statuses.contains(&x.status))
+            .filter(|x| match self.older_than {
+                Some(age) => get_process_age_secs(x, now).is_some_and(|x| x > age.as_secs()),
+                None => true,
+            })
+            .filter(|x| self.labels.iter().all(|label| label.matches(&x.labels)))
+            .collect()
+    }
+}
+
+// only alive processes are ever selected, so only their statuses can be asked for
+pub fn parse_status(name: &str) -> Result<ProcessStatus, String> {
+    match name {
+        "running" => Ok(ProcessStatus::Running),
+        "unhealthy" => Ok(ProcessStatus::Unhealthy),
+        _ => Err(format!(r#"unknown status "{}""#, name)),
+    }
+}
+
+fn is_glob(value: &str) -> bool {
+    value.contains(['*', '?'])
+}
+
+// names are looked up first, so a process named like another's pid is still found by name
+fn get_identified_processes(identifier: &str, processes: Vec<ProcessInfo>) -> Vec<ProcessInfo> {
+    if is_glob(identifier) {
+        let pattern = get_glob_regex(identifier);
+        return processes
+            .into_iter()
+            .filter(|x| pattern.is_match(&x.name))
+            .collect();
+    }
+
+    let lookups: [fn(&ProcessInfo) -> &str; 3] = [|x| &x.name, |x| &x.id, |x| &x.pid];
+    lookups
+        .iter()
+        .map(|lookup| {
+            processes
+                .iter()
+                .filter(|x| lookup(x) == identifier)
+                .cloned()
+                .collect::<Vec<ProcessInfo>>()
+        })
+        .find(|x| !x.is_empty())
+        .unwrap_or_default()
+}
+
+// "*" matches any run of characters and "?" any single one, everything else is literal
+fn get_glob_regex(glob: &str) -> Regex {
+    let pattern: String = glob
+        .split('*')
+        .map(|part| {
+            part.split('?')
+                .map(regex::escape)
+                .collect::<Vec<String>>()
+                .join(".")
+        })
+        .collect::<Vec<String>>()
+        .join(".*");
+    Regex::new(&format!("^{}$", pattern)).expect("escaped glob should be a valid regex")
+}
+
+// older state files predate start times, /proc still knows them while it runs
+fn get_process_age_secs(process: &ProcessInfo, now: u64) -> Option<u64> {
+    match process.started_at {
+        Some(started_at) => Some(now.saturating_sub(started_at)),
+        None => proc_stats::read_process_stats(&process.pid).map(|x| x.uptime_secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::labels;
+    use crate::subcommands::STATUS_NAMES;
+
+    #[test]
+    fn glob_should_match_whole_names_only() {
+        let pattern = get_glob_regex("api-*");
+        assert!(pattern.is_match("api-users"));
+        assert!(pattern.is_match("api-"));
+        assert!(!pattern.is_match("old-api-users"));
+
+        let pattern = get_glob_regex("worker-?.v1");
+        assert!(pattern.is_match("worker-2.v1"));
+        assert!(!pattern.is_match("worker-2xv1"));
+        assert!(!pattern.is_match("worker-12.v1"));
+    }
+
+    #[test]
+    fn every_status_name_should_parse() {
+        for name in STATUS_NAMES {
+            assert!(parse_status(name).is_ok(), "{}", name);
+        }
+        assert!(parse_status("Running").is_err());
+        assert!(parse_status("stopped").is_err());
+    }
+
+    #[test]
+    fn identifier_should_prefer_names_over_ids_and_pids() {
+        let processes = vec![
+            get_test_process("web", "aaaa", "123", ProcessStatus::Running),
+            get_test_process("123", "bbbb", "456", ProcessStatus::Running),
+        ];
+
+        let selected = get_identified_processes("123", processes.clone());
+        assert_eq!(selected, [processes[1].clone()]);
+
+        let selected = get_identified_processes("aaaa", processes.clone());
+        assert_eq!(selected, [processes[0].clone()]);
+        assert!(get_identified_processes("web2", processes).is_empty());
+    }
+
+    #[test]
+    fn selector_should_match_every_given_filter() {
+        let processes = vec![
+            get_test_process("api-users", "aaaa", "1", ProcessStatus::Running),
+            get_test_process("api-orders", "bbbb", "2", ProcessStatus::Unhealthy),
+            get_test_process("web", "cccc", "3", ProcessStatus::Unhealthy),
+        ];
+        let selector = Selector {
+            identifier: Some("api-*".to_string()),
+            statuses: vec![ProcessStatus::Unhealthy],
+            ..Selector::default()
+        };
+
+        let names: Vec<String> = selector
+            .select(processes.clone(), 1000)
+            .into_iter()
+            .map(|x| x.name)
+            .collect();
+        assert_eq!(names, ["api-orders"]);
+        assert!(!selector.is_single_identifier());
+
+        // started 900 seconds before "now"
+        let selector = Selector {
+            older_than: Some(Duration::from_secs(600)),
+            ..Selector::default()
+        };
+        assert_eq!(selector.select(processes.clone(), 1000).len(), 3);
+        assert!(selector.select(processes, 400).is_empty());
+    }
+
+    #[test]
+    fn selector_should_match_every_given_label() {
+        let mut processes = vec![
+            get_test_process("api", "aaaa", "1", ProcessStatus::Running),
+            get_test_process("web", "bbbb", "2", ProcessStatus::Running),
+        ];
+        processes[0]
+            .labels
+            .insert("env".to_string(), "prod".to_string());
+        processes[0]
+            .labels
+            .insert("team".to_string(), "api".to_string());
+        processes[1]
+            .labels
+            .insert("env".to_string(), "prod".to_string());
+        let selector = Selector {
+            labels: vec![
+                labels::parse_label_selector("env=prod").unwrap(),
+                labels::parse_label_selector("team").unwrap(),
+            ],
+            ..Selector::default()
+        };
+
+        let selected = selector.select(processes, 1000);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].name, "api");
+    }
+
+    fn get_test_process(name: &str, id: &str, pid: &str, status: ProcessStatus) -> ProcessInfo {
+        let mut process: ProcessInfo = serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": name,
+            "pid": pid,
+            "status": "Running",
+            "started_at": 100,
+        }))
+        .unwrap();
+        process.status = status;
+        process
+    }
+}
@@ -0,0 +1,41 @@
+pub const DEFAULT_STOP_SIGNAL: &str = "TERM";
+
+const KNOWN_SIGNALS: [&str; 15] = [
+    "HUP", "INT", "QUIT", "ABRT", "KILL", "USR1", "USR2", "PIPE", "ALRM", "TERM", "CHLD", "CONT",
+    "STOP", "TSTP", "WINCH",
+];
+
+// accepts "TERM", "SIGTERM" or "sigterm" and returns the bare "TERM" form `kill -s` expects
+pub fn normalize_signal_name(signal: &str) -> Option<String> {
+    let signal = signal.trim().to_uppercase();
+    let signal = signal.strip_prefix("SIG").unwrap_or(&signal);
+
+    KNOWN_SIGNALS
+        .iter()
+        .find(|known| **known == signal)
+        .map(|known| known.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signal_names_should_be_normalized() {
+        for signal in ["TERM", "SIGTERM", "sigterm", " term "] {
+            assert_eq!(normalize_signal_name(signal), Some("TERM".to_string()));
+        }
+    }
+
+    #[test]
+    fn unknown_signal_names_should_be_none() {
+        for signal in ["", "SIG", "TERMINATE", "15"] {
+            assert_eq!(normalize_signal_name(signal), None);
+        }
+    }
+
+    #[test]
+    fn default_stop_signal_should_be_known() {
+        assert!(normalize_signal_name(DEFAULT_STOP_SIGNAL).is_some());
+    }
+}
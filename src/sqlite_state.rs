@@ -0,0 +1,418 @@
+use super::file_io::{self, ProcessLookup};
+use super::{generate_process_id, ProcessInfo};
+use rusqlite::{params, params_from_iter, Connection, Transaction};
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+type Processes = Vec<ProcessInfo>;
+
+// other eggsecutor invocations may be holding the write lock
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// the JSON state file is only imported when the database has to be created
+pub fn create_database_if_not_exists(path: &str, json_state_file_path: &str) -> io::Result<()> {
+    open_database(path, json_state_file_path)?;
+    Ok(())
+}
+
+pub fn get_all_processes(path: &str, json_state_file_path: &str) -> io::Result<Processes> {
+    let conn = open_database(path, json_state_file_path)?;
+    query_processes(&conn, "SELECT data FROM processes ORDER BY rowid", &[])
+}
+
+pub fn find_processes(
+    path: &str,
+    json_state_file_path: &str,
+    lookup: &ProcessLookup,
+) -> io::Result<Processes> {
+    let conn = open_database(path, json_state_file_path)?;
+    let (query, value) = match lookup {
+        ProcessLookup::Name(name) => (
+            "SELECT data FROM processes WHERE name = ?1 ORDER BY rowid",
+            name,
+        ),
+        ProcessLookup::Id(id) => (
+            "SELECT data FROM processes WHERE id = ?1 ORDER BY rowid",
+            id,
+        ),
+        ProcessLookup::Pid(pid) => (
+            "SELECT data FROM processes WHERE pid = ?1 ORDER BY rowid",
+            pid,
+        ),
+    };
+
+    query_processes(&conn, query, &[value])
+}
+
+pub fn insert_process(
+    path: &str,
+    json_state_file_path: &str,
+    process: &ProcessInfo,
+) -> io::Result<()> {
+    let mut conn = open_database(path, json_state_file_path)?;
+    let tx = conn.transaction().map_err(to_io_error)?;
+    insert_process_in_transaction(&tx, process)?;
+    tx.commit().map_err(to_io_error)
+}
+
+pub fn delete_process_by_pid(path: &str, json_state_file_path: &str, pid: &str) -> io::Result<()> {
+    let conn = open_database(path, json_state_file_path)?;
+    conn.execute("DELETE FROM processes WHERE pid = ?1", params![pid])
+        .map_err(to_io_error)?;
+    Ok(())
+}
+
+pub fn update_process_by_pid(
+    path: &str,
+    json_state_file_path: &str,
+    process: &ProcessInfo,
+) -> io::Result<()> {
+    let conn = open_database(path, json_state_file_path)?;
+    let data = serde_json::to_string(process)?;
+    conn.execute(
+        "UPDATE processes SET name = ?1, data = ?2 WHERE pid = ?3",
+        params![process.name, data, process.pid],
+    )
+    .map_err(to_io_error)?;
+    Ok(())
+}
+
+pub fn replace_all_processes(
+    path: &str,
+    json_state_file_path: &str,
+    processes: &[ProcessInfo],
+) -> io::Result<()> {
+    let mut conn = open_database(path, json_state_file_path)?;
+    let tx = conn.transaction().map_err(to_io_error)?;
+    tx.execute("DELETE FROM processes", [])
+        .map_err(to_io_error)?;
+    for process in processes {
+        insert_process_in_transaction(&tx, process)?;
+    }
+    tx.commit().map_err(to_io_error)
+}
+
+fn insert_process_in_transaction(tx: &Transaction, process: &ProcessInfo) -> io::Result<()> {
+    // records carried over from older state files may not have an id yet
+    let id = match process.id.is_empty() {
+        true => generate_process_id(),
+        false => process.id.clone(),
+    };
+    let data = serde_json::to_string(&ProcessInfo {
+        id: id.clone(),
+        ..process.clone()
+    })?;
+
+    tx.execute(
+        "INSERT OR REPLACE INTO processes (id, name, pid, data) VALUES (?1, ?2, ?3, ?4)",
+        params![id, process.name, process.pid, data],
+    )
+    .map_err(to_io_error)?;
+    Ok(())
+}
+
+fn open_database(path: &str, json_state_file_path: &str) -> io::Result<Connection> {
+    let mut conn = Connection::open(path).map_err(to_io_error)?;
+    conn.busy_timeout(BUSY_TIMEOUT).map_err(to_io_error)?;
+
+    if !is_schema_created(&conn)? {
+        create_schema_and_migrate(&mut conn, json_state_file_path)?;
+    }
+
+    Ok(conn)
+}
+
+fn is_schema_created(conn: &Connection) -> io::Result<bool> {
+    conn.query_row(
+        "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = 'processes'",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+    .map_err(to_io_error)
+}
+
+fn create_schema_and_migrate(conn: &mut Connection, json_state_file_path: &str) -> io::Result<()> {
+    let tx = conn.transaction().map_err(to_io_error)?;
+
+    // another invocation may have won the race to create the schema
+    if is_schema_created(&tx)? {
+        return Ok(());
+    }
+
+    tx.execute_batch(
+        "CREATE TABLE processes (
+            id TEXT PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
+            pid TEXT NOT NULL,
+            data TEXT NOT NULL
+        );
+        CREATE INDEX processes_name_idx ON processes (name);
+        CREATE INDEX processes_pid_idx ON processes (pid);",
+    )
+    .map_err(to_io_error)?;
+
+    // one-shot import of the JSON state file, only ever done on schema creation
+    for process in get_processes_to_migrate(json_state_file_path)? {
+        insert_process_in_transaction(&tx, &process)?;
+    }
+
+    tx.commit().map_err(to_io_error)
+}
+
+fn get_processes_to_migrate(json_state_file_path: &str) -> io::Result<Processes> {
+    match Path::new(json_state_file_path).exists() {
+        true => file_io::read_processes_from_json_file(json_state_file_path),
+        false => Ok(vec![]),
+    }
+}
+
+fn query_processes(conn: &Connection, query: &str, values: &[&str]) -> io::Result<Processes> {
+    let mut statement = conn.prepare(query).map_err(to_io_error)?;
+    let rows = statement
+        .query_map(params_from_iter(values), |row| row.get::<_, String>(0))
+        .map_err(to_io_error)?;
+
+    let mut processes = vec![];
+    for row in rows {
+        processes.push(deserialize_process(&row.map_err(to_io_error)?)?);
+    }
+    Ok(processes)
+}
+
+fn deserialize_process(data: &str) -> io::Result<ProcessInfo> {
+    Ok(serde_json::from_str(data)?)
+}
+
+fn to_io_error(err: rusqlite::Error) -> io::Error {
+    io::Error::other(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn database_should_be_created_if_not_exists() {
+        let test_db = TestDatabase::new();
+        assert!(!Path::new(&test_db.path).exists());
+
+        create_database_if_not_exists(&test_db.path, &test_db.json_path)
+            .expect("database creation returned err");
+
+        assert!(Path::new(&test_db.path).exists());
+        let conn = Connection::open(&test_db.path).expect("database could not be opened");
+        assert!(is_schema_created(&conn).unwrap());
+    }
+
+    #[test]
+    fn inserted_processes_should_be_returned_in_order() {
+        let test_db = TestDatabase::new();
+        insert_process(
+            &test_db.path,
+            &test_db.json_path,
+            &get_test_process("first", "1"),
+        )
+        .unwrap();
+        insert_process(
+            &test_db.path,
+            &test_db.json_path,
+            &get_test_process("second", "2"),
+        )
+        .unwrap();
+
+        let names: Vec<String> = get_all_processes(&test_db.path, &test_db.json_path)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.name)
+            .collect();
+
+        assert_eq!(names, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn inserting_process_without_id_should_assign_one() {
+        let test_db = TestDatabase::new();
+        let mut process = get_test_process("no-id", "1");
+        process.id = String::new();
+
+        insert_process(&test_db.path, &test_db.json_path, &process).unwrap();
+
+        let stored = find_processes(
+            &test_db.path,
+            &test_db.json_path,
+            &ProcessLookup::Name("no-id"),
+        )
+        .unwrap();
+        assert!(!stored[0].id.is_empty());
+    }
+
+    #[test]
+    fn find_processes_should_look_up_by_name_id_and_pid() {
+        let test_db = TestDatabase::new();
+        let process = get_test_process("lookup", "4242");
+        insert_process(&test_db.path, &test_db.json_path, &process).unwrap();
+
+        for lookup in [
+            ProcessLookup::Name("lookup"),
+            ProcessLookup::Id(&process.id),
+            ProcessLookup::Pid("4242"),
+        ] {
+            let found = find_processes(&test_db.path, &test_db.json_path, &lookup).unwrap();
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].id, process.id);
+        }
+
+        let missing = find_processes(
+            &test_db.path,
+            &test_db.json_path,
+            &ProcessLookup::Name("missing"),
+        )
+        .unwrap();
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn deleting_process_by_pid_should_only_remove_that_process() {
+        let test_db = TestDatabase::new();
+        insert_process(
+            &test_db.path,
+            &test_db.json_path,
+            &get_test_process("keep", "1"),
+        )
+        .unwrap();
+        insert_process(
+            &test_db.path,
+            &test_db.json_path,
+            &get_test_process("remove", "2"),
+        )
+        .unwrap();
+
+        delete_process_by_pid(&test_db.path, &test_db.json_path, "2").unwrap();
+
+        let processes = get_all_processes(&test_db.path, &test_db.json_path).unwrap();
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].name, "keep");
+    }
+
+    #[test]
+    fn updating_process_by_pid_should_replace_its_data() {
+        let test_db = TestDatabase::new();
+        let process = get_test_process("web", "1");
+        insert_process(&test_db.path, &test_db.json_path, &process).unwrap();
+
+        let renamed = ProcessInfo {
+            name: "api".to_string(),
+            restart_count: 2,
+            ..process
+        };
+        update_process_by_pid(&test_db.path, &test_db.json_path, &renamed).unwrap();
+
+        let processes = get_all_processes(&test_db.path, &test_db.json_path).unwrap();
+        assert_eq!(processes, vec![renamed]);
+    }
+
+    #[test]
+    fn replacing_all_processes_with_empty_vec_should_clear_table() {
+        let test_db = TestDatabase::new();
+        insert_process(
+            &test_db.path,
+            &test_db.json_path,
+            &get_test_process("old", "1"),
+        )
+        .unwrap();
+
+        replace_all_processes(&test_db.path, &test_db.json_path, &[]).unwrap();
+
+        assert!(get_all_processes(&test_db.path, &test_db.json_path)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn processes_to_migrate_should_be_empty_if_no_json_file() {
+        let missing_path = format!("{}.testfile", Uuid::new_v4().to_simple());
+        let processes = get_processes_to_migrate(&missing_path).unwrap();
+        assert!(processes.is_empty());
+    }
+
+    #[test]
+    fn processes_to_migrate_should_be_read_from_json_file() {
+        let json_path = format!("{}.testfile", Uuid::new_v4().to_simple());
+        fs::write(
+            &json_path,
+            r#"[{"name":"TEST_PROCES","pid":"0000","status":"Running"}]"#,
+        )
+        .unwrap();
+
+        let processes = get_processes_to_migrate(&json_path);
+        let _result = fs::remove_file(&json_path);
+
+        assert_eq!(processes.unwrap()[0].name, "TEST_PROCES");
+    }
+
+    #[test]
+    fn new_database_should_import_its_json_state_file() {
+        let test_db = TestDatabase::new();
+        fs::write(&test_db.json_path, get_test_json_state("migrated")).unwrap();
+
+        create_database_if_not_exists(&test_db.path, &test_db.json_path).unwrap();
+
+        let processes = get_all_processes(&test_db.path, &test_db.json_path).unwrap();
+        assert_eq!(processes[0].name, "migrated");
+    }
+
+    #[test]
+    fn new_namespaced_database_should_not_import_default_json_state_file() {
+        let default_db = TestDatabase::new();
+        fs::write(&default_db.json_path, get_test_json_state("default")).unwrap();
+        let namespaced_db = TestDatabase {
+            path: format!("{}.web", default_db.path),
+            json_path: format!("{}.web", default_db.json_path),
+        };
+
+        create_database_if_not_exists(&namespaced_db.path, &namespaced_db.json_path).unwrap();
+
+        let processes = get_all_processes(&namespaced_db.path, &namespaced_db.json_path).unwrap();
+        assert!(processes.is_empty());
+    }
+
+    struct TestDatabase {
+        path: String,
+        json_path: String,
+    }
+
+    impl Drop for TestDatabase {
+        fn drop(&mut self) {
+            let _result = fs::remove_file(&self.path);
+            let _result = fs::remove_file(&self.json_path);
+        }
+    }
+
+    impl TestDatabase {
+        fn new() -> Self {
+            let path = format!("{}.testdb", Uuid::new_v4().to_simple());
+            Self {
+                json_path: format!("{}.json", path),
+                path,
+            }
+        }
+    }
+
+    fn get_test_json_state(name: &str) -> String {
+        format!(r#"[{{"name":"{}","pid":"0000","status":"Running"}}]"#, name)
+    }
+
+    fn get_test_process(name: &str, pid: &str) -> ProcessInfo {
+        serde_json::from_str(&format!(
+            r#"{{"id":"{}","name":"{}","pid":"{}","status":"Running"}}"#,
+            generate_process_id(),
+            name,
+            pid
+        ))
+        .expect("test process data should be valid")
+    }
+}
@@ -1,13 +1,246 @@
-use clap::{App, Arg};
+// nothing outside of clap is used here, so the build script can include this
+// file as is to write the man pages
+use clap::{App, AppSettings, Arg};
+
+const PROGRAM_TITLE: &str = "eggsecutor";
+const VERSION: &str = "1.0";
+const AUTHOR: &str = "astherath <me@felipearce.dev>";
+const ABOUT: &str = "A friendly background process task manager";
+
+pub const DEFAULT_COLUMNS: &str = "name,id,pid,status,uptime,cpu,rss,threads,fds";
+pub const DEFAULT_INTERVAL: &str = "2s";
+pub const OUTPUT_FORMAT_NAMES: [&str; 3] = ["table", "json", "yaml"];
+pub const COLOR_CHOICE_NAMES: [&str; 3] = ["auto", "always", "never"];
+// the statuses a tracked, still alive process can have
+pub const STATUS_NAMES: [&str; 2] = ["running", "unhealthy"];
+pub const SHELL_NAMES: [&str; 3] = ["bash", "zsh", "fish"];
+// the hidden subcommand the scripts run for the names and ids of tracked processes
+pub const PROCESS_IDENTIFIERS_SUBCOMMAND_NAME: &str = "__complete";
+
+pub fn get_app() -> App<'static> {
+    let app = App::new(PROGRAM_TITLE)
+        .version(VERSION)
+        .author(AUTHOR)
+        .about(ABOUT)
+        .setting(AppSettings::ArgRequiredElseHelp);
+
+    let app = get_all_subcommands()
+        .into_iter()
+        .fold(app, |acc, subcommand| acc.subcommand(subcommand));
+    get_global_args()
+        .into_iter()
+        .fold(app, |acc, arg| acc.arg(arg))
+}
+
 pub fn get_all_subcommands<'a>() -> Vec<App<'a>> {
     vec![
         get_hatch_subcommand(),
         get_list_processes_subcommand(),
+        get_inspect_subcommand(),
+        get_top_subcommand(),
         get_stop_process_subcommand(),
+        get_restart_process_subcommand(),
+        get_signal_process_subcommand(),
+        get_label_subcommand(),
+        get_completions_subcommand(),
+        get_man_subcommand(),
         get_clear_state_subcommand(),
+        get_namespaces_subcommand(),
+        get_where_subcommand(),
+        get_export_subcommand(),
+        get_import_subcommand(),
+        get_up_subcommand(),
+        get_down_subcommand(),
+        get_plan_subcommand(),
+        get_apply_subcommand(),
+        get_check_subcommand(),
+    ]
+}
+
+pub fn get_global_args<'a>() -> Vec<Arg<'a>> {
+    vec![get_namespace_arg(), get_output_arg(), get_color_arg()]
+}
+
+fn get_color_arg<'a>() -> Arg<'a> {
+    Arg::new("color")
+        .long("color")
+        .help("Color the status column, by default on terminals without NO_COLOR set [env: EGGSECUTOR_COLOR]")
+        .takes_value(true)
+        .value_name("WHEN")
+        .possible_values(COLOR_CHOICE_NAMES)
+        .global(true)
+}
+
+fn get_output_arg<'a>() -> Arg<'a> {
+    Arg::new("output")
+        .short('o')
+        .long("output")
+        .help("Print results and errors as a table or structured data [env: EGGSECUTOR_OUTPUT]")
+        .takes_value(true)
+        .value_name("FORMAT")
+        .possible_values(OUTPUT_FORMAT_NAMES)
+        .global(true)
+}
+
+fn get_namespace_arg<'a>() -> Arg<'a> {
+    Arg::new("namespace")
+        .short('N')
+        .long("namespace")
+        .help("Use an isolated set of tracked processes [env: EGGSECUTOR_NAMESPACE]")
+        .takes_value(true)
+        .value_name("NAMESPACE")
+        .global(true)
+}
+
+fn get_export_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "export";
+    const ABOUT: &str = "write the launch specs of all tracked processes to a portable file";
+
+    App::new(SUBCOMMAND_NAME)
+        .about(ABOUT)
+        .arg(
+            Arg::new("file")
+                .help("File to write to, printed to stdout if not given")
+                .takes_value(true)
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .help("File format, inferred from the file extension if not given")
+                .takes_value(true)
+                .possible_values(["json", "toml"]),
+        )
+}
+
+fn get_import_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "import";
+    const ABOUT: &str = "hatch all of the processes in an exported file";
+
+    App::new(SUBCOMMAND_NAME)
+        .about(ABOUT)
+        .arg(
+            Arg::new("file")
+                .help("File created by the export subcommand")
+                .required(true)
+                .takes_value(true)
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("only print what would be done"),
+        )
+        .arg(
+            Arg::new("on-conflict")
+                .long("on-conflict")
+                .help("What to do when a process with the same name is already running")
+                .takes_value(true)
+                .possible_values(["fail", "skip", "rename", "replace"])
+                .default_value("fail"),
+        )
+}
+
+fn get_up_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "up";
+    const ABOUT: &str = "hatch the processes declared in a manifest that aren't running yet";
+
+    get_manifest_args()
+        .into_iter()
+        .fold(App::new(SUBCOMMAND_NAME).about(ABOUT), |acc, arg| {
+            acc.arg(arg)
+        })
+        .arg(
+            Arg::new("formation")
+                .long("formation")
+                .help("Instance counts per Procfile process type, e.g. web=2,worker=1")
+                .takes_value(true)
+                .value_name("FORMATION")
+                .requires("procfile"),
+        )
+}
+
+fn get_down_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "down";
+    const ABOUT: &str = "stop the processes declared in a manifest";
+
+    get_manifest_args()
+        .into_iter()
+        .fold(App::new(SUBCOMMAND_NAME).about(ABOUT), |acc, arg| {
+            acc.arg(arg)
+        })
+}
+
+fn get_manifest_args<'a>() -> Vec<Arg<'a>> {
+    vec![
+        Arg::new("names")
+            .help("Names of the processes to use, all of them if not given")
+            .takes_value(true)
+            .multiple_values(true)
+            .value_name("NAMES"),
+        get_manifest_file_arg(),
+        Arg::new("procfile")
+            .long("procfile")
+            .help("Procfile of `name: command` lines to use instead of the manifest")
+            .takes_value(true)
+            .value_name("PROCFILE"),
     ]
 }
 
+fn get_manifest_file_arg<'a>() -> Arg<'a> {
+    Arg::new("file")
+        .short('f')
+        .long("file")
+        .help("Manifest declaring the processes")
+        .takes_value(true)
+        .value_name("FILE")
+        .default_value("Eggfile.toml")
+}
+
+fn get_plan_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "plan";
+    const ABOUT: &str = "show what apply would start, stop and restart to match a manifest";
+
+    App::new(SUBCOMMAND_NAME)
+        .about(ABOUT)
+        .arg(get_manifest_file_arg())
+}
+
+fn get_apply_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "apply";
+    const ABOUT: &str = "start, stop and restart processes until they match a manifest";
+
+    App::new(SUBCOMMAND_NAME)
+        .about(ABOUT)
+        .arg(get_manifest_file_arg())
+}
+
+fn get_check_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "check";
+    const ABOUT: &str = "run the health checks of tracked processes";
+
+    App::new(SUBCOMMAND_NAME).about(ABOUT).arg(
+        Arg::new("watch")
+            .short('w')
+            .long("watch")
+            .help("Keep checking each process on its own interval until interrupted, nothing else runs the checks"),
+    )
+}
+
+fn get_where_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "where";
+    const ABOUT: &str = "show which state file is in use and why";
+
+    App::new(SUBCOMMAND_NAME).about(ABOUT)
+}
+
+fn get_namespaces_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "namespaces";
+    const ABOUT: &str = "list all namespaces and how many processes each is tracking";
+
+    App::new(SUBCOMMAND_NAME).about(ABOUT)
+}
+
 fn get_clear_state_subcommand<'a>() -> App<'a> {
     const SUBCOMMAND_NAME: &str = "clear";
     const ABOUT: &str = "stops all of the processes being tracked and clears the tracking list";
@@ -21,37 +254,482 @@ fn get_clear_state_subcommand<'a>() -> App<'a> {
 
 fn get_stop_process_subcommand<'a>() -> App<'a> {
     const SUBCOMMAND_NAME: &str = "stop";
-    const ABOUT: &str = "stop a process by name or pid";
+    const ABOUT: &str = "stop processes by name, id, pid or selectors";
+
+    App::new(SUBCOMMAND_NAME)
+        .about(ABOUT)
+        .arg(get_selected_identifier_arg(
+            "Name, id or pid of process to stop, or a glob of names like \"api-*\"",
+        ))
+        .args(get_selector_args())
+        .args(get_bulk_operation_args())
+}
+
+fn get_restart_process_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "restart";
+    const ABOUT: &str = "stop processes and hatch them again with the same launch spec";
+
+    App::new(SUBCOMMAND_NAME)
+        .about(ABOUT)
+        .arg(get_selected_identifier_arg(
+            "Name, id or pid of process to restart, or a glob of names like \"api-*\"",
+        ))
+        .args(get_selector_args())
+        .args(get_bulk_operation_args())
+}
+
+fn get_signal_process_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "signal";
+    const ABOUT: &str = "send a signal to processes";
+
+    // lets the identifier be left out in favor of selectors, e.g. `signal --all HUP`
+    App::new(SUBCOMMAND_NAME)
+        .about(ABOUT)
+        .setting(AppSettings::AllowMissingPositional)
+        .arg(get_selected_identifier_arg(
+            "Name, id or pid of process to signal, or a glob of names like \"api-*\"",
+        ))
+        .arg(
+            Arg::new("signal")
+                .help("Signal to send, e.g. HUP or SIGUSR1")
+                .required(true)
+                .takes_value(true)
+                .value_name("SIGNAL"),
+        )
+        .args(get_selector_args())
+        .args(get_bulk_operation_args())
+}
+
+fn get_label_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "label";
+    const ABOUT: &str = "add or remove labels on running processes";
+
+    App::new(SUBCOMMAND_NAME)
+        .about(ABOUT)
+        .setting(AppSettings::AllowMissingPositional)
+        .arg(get_selected_identifier_arg(
+            "Name, id or pid of process to label, or a glob of names like \"api-*\"",
+        ))
+        .arg(
+            Arg::new("labels")
+                .help("Labels to set as KEY=VALUE, or to remove as KEY-")
+                .required(true)
+                .takes_value(true)
+                .multiple_values(true)
+                .value_name("LABEL"),
+        )
+        .args(get_selector_args())
+        .args(get_bulk_operation_args())
+}
+
+// optional as long as some other selector picks the processes
+fn get_selected_identifier_arg(help: &str) -> Arg<'_> {
+    Arg::new("process identifier")
+        .help(help)
+        .required_unless_present_any(["all", "status", "older-than", "tag"])
+        .takes_value(true)
+        .value_name("PROCESS_IDENTIFIER")
+}
+
+// a process has to match every one of these that's given
+fn get_selector_args<'a>() -> Vec<Arg<'a>> {
+    vec![
+        Arg::new("status")
+            .long("status")
+            .takes_value(true)
+            .value_name("STATUS")
+            .possible_values(STATUS_NAMES)
+            .multiple_occurrences(true)
+            .help("only processes with this status, can be given more than once"),
+        Arg::new("tag")
+            .long("tag")
+            .takes_value(true)
+            .value_name("KEY[=VALUE]")
+            .multiple_occurrences(true)
+            .help("only processes with this label value, or with the label at all without one"),
+        Arg::new("older-than")
+            .long("older-than")
+            .takes_value(true)
+            .value_name("DURATION")
+            .help("only processes started longer ago than this, e.g. 1h or 30m"),
+    ]
+}
+
+fn get_bulk_operation_args<'a>() -> Vec<Arg<'a>> {
+    vec![
+        Arg::new("all")
+            .long("all")
+            .conflicts_with("process identifier")
+            .help("every running process, narrowed down by any other selectors"),
+        Arg::new("yes")
+            .short('y')
+            .long("yes")
+            .help("go ahead when more than one process matches"),
+    ]
+}
+
+fn get_completions_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "completions";
+    const ABOUT: &str = "print a shell completion script, including tracked process names";
 
     App::new(SUBCOMMAND_NAME).about(ABOUT).arg(
-        Arg::new("process identifier")
-            .help("Name or pid of process to stop")
+        Arg::new("shell")
+            .help("Shell to complete in")
             .required(true)
             .takes_value(true)
-            .value_name("PROCESS_IDENTIFIER"),
+            .value_name("SHELL")
+            .possible_values(SHELL_NAMES),
     )
 }
 
+fn get_man_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "man";
+    const ABOUT: &str = "print the man page of eggsecutor or one of its subcommands";
+
+    App::new(SUBCOMMAND_NAME)
+        .about(ABOUT)
+        .arg(
+            Arg::new("subcommand")
+                .help("Subcommand to print the page of, e.g. stop, instead of the program's")
+                .takes_value(true)
+                .value_name("SUBCOMMAND")
+                .conflicts_with("dir"),
+        )
+        .arg(
+            Arg::new("dir")
+                .long("dir")
+                .help("Write every page to this directory instead, e.g. to install them")
+                .takes_value(true)
+                .value_name("DIR"),
+        )
+}
+
+// what the completion scripts call, not meant to be run by hand, so it's kept out
+// of `get_all_subcommands` and the scripts generated from them
+pub fn get_process_identifiers_subcommand<'a>() -> App<'a> {
+    const ABOUT: &str = "print the names and ids of tracked processes, for shell completion";
+
+    App::new(PROCESS_IDENTIFIERS_SUBCOMMAND_NAME)
+        .about(ABOUT)
+        .hide(true)
+}
+
+fn get_top_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "top";
+    const ABOUT: &str = "live view of the processes, with their logs and keys to control them";
+
+    App::new(SUBCOMMAND_NAME).about(ABOUT)
+}
+
 fn get_list_processes_subcommand<'a>() -> App<'a> {
     const SUBCOMMAND_NAME: &str = "list";
     const ABOUT: &str = "list all managed processes";
 
-    App::new(SUBCOMMAND_NAME).about(ABOUT)
+    App::new(SUBCOMMAND_NAME)
+        .about(ABOUT)
+        .arg(
+            Arg::new("process identifier")
+                .help("Only list the process with this name, id or pid, or whose names match a glob like \"api-*\"")
+                .takes_value(true)
+                .value_name("PROCESS_IDENTIFIER"),
+        )
+        .args(get_selector_args())
+        .arg(
+            Arg::new("all-namespaces")
+                .long("all-namespaces")
+                .help("list the processes of every namespace, not just the current one"),
+        )
+        .arg(
+            Arg::new("columns")
+                .long("columns")
+                .takes_value(true)
+                .value_name("COLUMNS")
+                .default_value(DEFAULT_COLUMNS)
+                .help("comma separated columns to show, out of name, id, pid, status, uptime, cpu, rss, threads, fds, cgroup, labels and label:KEY"),
+        )
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .takes_value(true)
+                .value_name("[-]COLUMN")
+                .allow_hyphen_values(true)
+                .help("sort by a column, descending with a leading \"-\", e.g. --sort -rss"),
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .conflicts_with("all-namespaces")
+                .help("keep redrawing the table, highlighting rows whose status changed"),
+        )
+        .arg(
+            Arg::new("interval")
+                .long("interval")
+                .takes_value(true)
+                .value_name("DURATION")
+                .default_value(DEFAULT_INTERVAL)
+                .help("how often --watch refreshes, e.g. 500ms or 5s"),
+        )
+        .arg(
+            Arg::new("exit-on-death")
+                .long("exit-on-death")
+                .requires("watch")
+                .help("with --watch, exit with status 1 as soon as a process dies"),
+        )
 }
 
-fn get_hatch_subcommand<'a>() -> App<'a> {
-    const SUBCOMMAND_NAME: &str = "hatch";
-    const ABOUT: &str = "start managing a binary process";
+fn get_inspect_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "inspect";
+    const ABOUT: &str = "show everything known about a single process";
 
     App::new(SUBCOMMAND_NAME).about(ABOUT).arg(
-        Arg::new("file")
-            .help("Sets the input file to use")
+        Arg::new("process identifier")
+            .help("Name, id or pid of process to inspect")
             .required(true)
             .takes_value(true)
-            .value_name("INPUT"),
+            .value_name("PROCESS_IDENTIFIER"),
     )
 }
 
+fn get_hatch_subcommand<'a>() -> App<'a> {
+    const SUBCOMMAND_NAME: &str = "hatch";
+    const ABOUT: &str = "start managing a binary process";
+
+    App::new(SUBCOMMAND_NAME)
+        .about(ABOUT)
+        .arg(
+            Arg::new("file")
+                .help("Sets the input file to use")
+                .required_unless_present("shell")
+                .conflicts_with("shell")
+                .takes_value(true)
+                .value_name("INPUT"),
+        )
+        .arg(
+            Arg::new("shell")
+                .long("shell")
+                .help("Run a command line through the shell instead of an input file")
+                .takes_value(true)
+                .value_name("COMMAND")
+                .requires("name"),
+        )
+        .arg(
+            Arg::new("name")
+                .long("name")
+                .help("Track the process under this name instead of the input file's")
+                .takes_value(true)
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("tag")
+                .long("tag")
+                .help("Label the process, e.g. --tag env=prod, can be given more than once")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .value_name("KEY=VALUE"),
+        )
+        .arg(
+            Arg::new("after")
+                .long("after")
+                .help("Wait for the named process to be running before hatching")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("wait-ready")
+                .long("wait-ready")
+                .help("Only report success once the process is ready, alive for 500ms by default"),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .help("Seconds to wait for readiness before stopping the process")
+                .takes_value(true)
+                .value_name("SECONDS")
+                .default_value("30"),
+        )
+        .args(get_environment_args())
+        .args(get_resource_limit_args())
+        .args(get_cgroup_limit_args())
+        .args(get_readiness_check_args())
+        .args(get_health_check_args())
+}
+
+// names match limits::LimitResource::get_arg_name
+fn get_resource_limit_args<'a>() -> Vec<Arg<'a>> {
+    let limit_args = [
+        ("limit-nofile", "Limit the number of open file descriptors"),
+        (
+            "limit-as",
+            "Limit the address space size in bytes, e.g. 512M",
+        ),
+        ("limit-cpu", "Limit the CPU time in seconds"),
+        (
+            "limit-core",
+            "Limit the core dump size in bytes, 0 disables them",
+        ),
+        ("limit-nproc", "Limit the number of processes of the user"),
+        (
+            "limit-fsize",
+            "Limit the size of files the process writes in bytes",
+        ),
+    ];
+
+    limit_args
+        .iter()
+        .map(|&(name, help)| {
+            Arg::new(name)
+                .long(name)
+                .help(help)
+                .takes_value(true)
+                .value_name("LIMIT[:HARD]")
+        })
+        .collect()
+}
+
+fn get_cgroup_limit_args<'a>() -> Vec<Arg<'a>> {
+    vec![
+        Arg::new("memory-max")
+            .long("memory-max")
+            .help("Limit the memory of the process and its children, e.g. 512M")
+            .takes_value(true)
+            .value_name("SIZE"),
+        Arg::new("cpu-max")
+            .long("cpu-max")
+            .help("Limit the CPUs the process and its children may use, e.g. 0.5")
+            .takes_value(true)
+            .value_name("CPUS"),
+        Arg::new("pids-max")
+            .long("pids-max")
+            .help("Limit the number of processes the process may have running")
+            .takes_value(true)
+            .value_name("COUNT"),
+    ]
+}
+
+fn get_environment_args<'a>() -> Vec<Arg<'a>> {
+    vec![
+        Arg::new("cwd")
+            .long("cwd")
+            .help("Working directory to start the process in")
+            .takes_value(true)
+            .value_name("DIR"),
+        Arg::new("env")
+            .short('e')
+            .long("env")
+            .help("Set an environment variable for the process")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .value_name("KEY=VALUE"),
+        Arg::new("env-file")
+            .long("env-file")
+            .help("Set the environment variables listed in a .env file")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .value_name("FILE"),
+        Arg::new("clear-env")
+            .long("clear-env")
+            .help("Don't inherit the environment, only pass variables set explicitly or kept"),
+        Arg::new("keep-env")
+            .long("keep-env")
+            .help("Inherit this variable even with --clear-env")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .value_name("NAME")
+            .requires("clear-env"),
+    ]
+}
+
+fn get_health_check_args<'a>() -> Vec<Arg<'a>> {
+    const PROBE_ARGS: [&str; 3] = ["health-cmd", "health-tcp", "health-http"];
+
+    let probe_args = [
+        (
+            "health-cmd",
+            "COMMAND",
+            "Health check passing when a shell command exits 0",
+        ),
+        (
+            "health-tcp",
+            "PORT",
+            "Health check passing when a local TCP port accepts connections",
+        ),
+        (
+            "health-http",
+            "PORT[/PATH]",
+            "Health check passing when a local HTTP GET returns 2xx",
+        ),
+    ]
+    .iter()
+    .map(|&(name, value_name, help)| {
+        Arg::new(name)
+            .long(name)
+            .help(help)
+            .takes_value(true)
+            .value_name(value_name)
+            .conflicts_with_all(
+                &PROBE_ARGS
+                    .iter()
+                    .filter(|x| **x != name)
+                    .copied()
+                    .collect::<Vec<_>>(),
+            )
+    });
+
+    probe_args
+        .chain([
+            Arg::new("health-interval")
+                .long("health-interval")
+                .help("Seconds between health checks while `check --watch` runs")
+                .takes_value(true)
+                .value_name("SECONDS")
+                .default_value("30"),
+            Arg::new("health-retries")
+                .long("health-retries")
+                .help("Consecutive failed checks before the process is marked unhealthy")
+                .takes_value(true)
+                .value_name("COUNT")
+                .default_value("3"),
+            Arg::new("restart-unhealthy")
+                .long("restart-unhealthy")
+                .help("Restart the process once it is marked unhealthy"),
+        ])
+        .collect()
+}
+
+// giving any of these implies --wait-ready; all of them have to pass
+fn get_readiness_check_args<'a>() -> Vec<Arg<'a>> {
+    [
+        (
+            "ready-alive",
+            "MS",
+            "Ready once the process has stayed alive this long",
+        ),
+        (
+            "ready-tcp",
+            "PORT",
+            "Ready once a local TCP port accepts connections",
+        ),
+        (
+            "ready-http",
+            "PORT[/PATH]",
+            "Ready once a local HTTP GET returns 2xx",
+        ),
+        ("ready-file", "PATH", "Ready once a file exists"),
+        ("ready-log", "REGEX", "Ready once a new log line matches"),
+    ]
+    .iter()
+    .map(|&(name, value_name, help)| {
+        Arg::new(name)
+            .long(name)
+            .help(help)
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .value_name(value_name)
+    })
+    .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -76,13 +754,214 @@ mod tests {
         #[test]
         fn subcommand_should_return_app_instance() {
             let expected_name = "stop";
-            let expected_about = "stop a process by name or pid";
+            let expected_about = "stop processes by name, id, pid or selectors";
             utils::test_subcommand_should_return_app_instance(
                 get_stop_process_subcommand,
                 expected_name,
                 expected_about,
             );
         }
+
+        #[test]
+        fn subcommand_should_need_an_identifier_or_selector() {
+            let app = get_stop_process_subcommand();
+            assert!(app.clone().try_get_matches_from(["stop"]).is_err());
+            assert!(app
+                .clone()
+                .try_get_matches_from(["stop", "web", "--all"])
+                .is_err());
+
+            let matches = app
+                .try_get_matches_from(["stop", "--status", "unhealthy", "--older-than", "1h", "-y"])
+                .unwrap();
+            assert_eq!(matches.value_of("process identifier"), None);
+            assert!(matches.is_present("yes"));
+        }
+    }
+
+    mod restart_subcommand {
+        use super::get_restart_process_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "restart";
+            let expected_about = "stop processes and hatch them again with the same launch spec";
+            utils::test_subcommand_should_return_app_instance(
+                get_restart_process_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+    }
+
+    mod signal_subcommand {
+        use super::get_signal_process_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "signal";
+            let expected_about = "send a signal to processes";
+            utils::test_subcommand_should_return_app_instance(
+                get_signal_process_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+
+        #[test]
+        fn subcommand_should_require_identifier_and_signal() {
+            let app = get_signal_process_subcommand();
+            assert!(app.clone().try_get_matches_from(["signal", "web"]).is_err());
+
+            let matches = app
+                .clone()
+                .try_get_matches_from(["signal", "web", "HUP"])
+                .unwrap();
+            assert_eq!(matches.value_of("signal"), Some("HUP"));
+
+            // with a selector the only positional left is the signal
+            let matches = app
+                .try_get_matches_from(["signal", "--all", "HUP"])
+                .unwrap();
+            assert_eq!(matches.value_of("process identifier"), None);
+            assert_eq!(matches.value_of("signal"), Some("HUP"));
+        }
+    }
+
+    mod label_subcommand {
+        use super::get_label_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "label";
+            let expected_about = "add or remove labels on running processes";
+            utils::test_subcommand_should_return_app_instance(
+                get_label_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+
+        #[test]
+        fn subcommand_should_take_labels_after_identifier_or_selectors() {
+            let app = get_label_subcommand();
+            let matches = app
+                .clone()
+                .try_get_matches_from(["label", "web", "env=prod", "old-"])
+                .unwrap();
+            assert_eq!(matches.value_of("process identifier"), Some("web"));
+            let labels: Vec<&str> = matches.values_of("labels").unwrap().collect();
+            assert_eq!(labels, ["env=prod", "old-"]);
+
+            let matches = app
+                .clone()
+                .try_get_matches_from(["label", "--tag", "team=api", "env=prod"])
+                .unwrap();
+            assert_eq!(matches.value_of("process identifier"), None);
+            assert_eq!(matches.values_of("labels").unwrap().count(), 1);
+
+            assert!(app.try_get_matches_from(["label", "web"]).is_err());
+        }
+    }
+
+    mod completions_subcommand {
+        use super::get_completions_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "completions";
+            let expected_about = "print a shell completion script, including tracked process names";
+            utils::test_subcommand_should_return_app_instance(
+                get_completions_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+
+        #[test]
+        fn subcommand_should_only_accept_known_shells() {
+            let app = get_completions_subcommand();
+            assert!(app
+                .clone()
+                .try_get_matches_from(["completions", "zsh"])
+                .is_ok());
+            assert!(app
+                .try_get_matches_from(["completions", "powershell"])
+                .is_err());
+        }
+    }
+
+    mod man_subcommand {
+        use super::get_man_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "man";
+            let expected_about = "print the man page of eggsecutor or one of its subcommands";
+            utils::test_subcommand_should_return_app_instance(
+                get_man_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+
+        #[test]
+        fn subcommand_should_be_optional() {
+            let app = get_man_subcommand();
+            let matches = app.clone().try_get_matches_from(["man"]).unwrap();
+            assert_eq!(matches.value_of("subcommand"), None);
+
+            let matches = app.clone().try_get_matches_from(["man", "stop"]).unwrap();
+            assert_eq!(matches.value_of("subcommand"), Some("stop"));
+
+            assert!(app
+                .try_get_matches_from(["man", "stop", "--dir", "man"])
+                .is_err());
+        }
+    }
+
+    mod process_identifiers_subcommand {
+        use super::get_process_identifiers_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "__complete";
+            let expected_about =
+                "print the names and ids of tracked processes, for shell completion";
+            utils::test_subcommand_should_return_app_instance(
+                get_process_identifiers_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+
+        #[test]
+        fn subcommand_should_be_hidden() {
+            assert!(get_process_identifiers_subcommand().is_hide_set());
+        }
+    }
+
+    mod top_subcommand {
+        use super::get_top_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "top";
+            let expected_about =
+                "live view of the processes, with their logs and keys to control them";
+            utils::test_subcommand_should_return_app_instance(
+                get_top_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
     }
 
     mod list_subcommand {
@@ -99,6 +978,71 @@ mod tests {
                 expected_about,
             );
         }
+
+        #[test]
+        fn columns_should_default_and_sort_should_accept_leading_dash() {
+            let matches = get_list_processes_subcommand()
+                .try_get_matches_from(["list", "--sort", "-rss"])
+                .unwrap();
+
+            assert_eq!(matches.value_of("columns"), Some(super::DEFAULT_COLUMNS));
+            assert_eq!(matches.value_of("sort"), Some("-rss"));
+        }
+
+        #[test]
+        fn subcommand_should_filter_by_identifier_and_selectors() {
+            let matches = get_list_processes_subcommand()
+                .try_get_matches_from([
+                    "list",
+                    "api-*",
+                    "--status",
+                    "running",
+                    "--status",
+                    "unhealthy",
+                ])
+                .unwrap();
+
+            assert_eq!(matches.value_of("process identifier"), Some("api-*"));
+            let statuses: Vec<&str> = matches.values_of("status").unwrap().collect();
+            assert_eq!(statuses, ["running", "unhealthy"]);
+        }
+
+        #[test]
+        fn exit_on_death_should_require_watch() {
+            let app = get_list_processes_subcommand();
+            assert!(app
+                .clone()
+                .try_get_matches_from(["list", "--exit-on-death"])
+                .is_err());
+
+            let matches = app
+                .try_get_matches_from(["list", "--watch", "--exit-on-death"])
+                .unwrap();
+            assert_eq!(matches.value_of("interval"), Some(super::DEFAULT_INTERVAL));
+        }
+    }
+
+    mod inspect_subcommand {
+        use super::get_inspect_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "inspect";
+            let expected_about = "show everything known about a single process";
+            utils::test_subcommand_should_return_app_instance(
+                get_inspect_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+
+        #[test]
+        fn subcommand_should_require_identifier() {
+            assert!(get_inspect_subcommand()
+                .try_get_matches_from(["inspect"])
+                .is_err());
+        }
     }
 
     mod hatch_subcommand {
@@ -115,6 +1059,131 @@ mod tests {
                 expected_about,
             );
         }
+
+        #[test]
+        fn after_should_accept_multiple_occurrences() {
+            let matches = get_hatch_subcommand()
+                .try_get_matches_from(["hatch", "api", "--after", "db", "--after", "proxy"])
+                .expect("hatch args should be valid");
+
+            let after: Vec<&str> = matches.values_of("after").unwrap().collect();
+            assert_eq!(after, vec!["db", "proxy"]);
+        }
+
+        #[test]
+        fn readiness_args_should_be_optional_with_default_timeout() {
+            let matches = get_hatch_subcommand()
+                .try_get_matches_from(["hatch", "api"])
+                .expect("hatch args should be valid");
+
+            assert!(!matches.is_present("wait-ready"));
+            assert_eq!(matches.value_of("timeout"), Some("30"));
+        }
+
+        #[test]
+        fn readiness_checks_should_be_accepted_together() {
+            let matches = get_hatch_subcommand()
+                .try_get_matches_from([
+                    "hatch",
+                    "api",
+                    "--ready-tcp",
+                    "8000",
+                    "--ready-log",
+                    "listening",
+                    "--timeout",
+                    "5",
+                ])
+                .expect("hatch args should be valid");
+
+            assert_eq!(matches.value_of("ready-tcp"), Some("8000"));
+            assert_eq!(matches.value_of("ready-log"), Some("listening"));
+            assert_eq!(matches.value_of("timeout"), Some("5"));
+        }
+
+        #[test]
+        fn shell_should_replace_input_file_and_require_name() {
+            let matches = get_hatch_subcommand()
+                .try_get_matches_from([
+                    "hatch",
+                    "--shell",
+                    "python -m http.server 8000",
+                    "--name",
+                    "files",
+                ])
+                .expect("hatch args should be valid");
+            assert_eq!(
+                matches.value_of("shell"),
+                Some("python -m http.server 8000")
+            );
+
+            let without_name =
+                get_hatch_subcommand().try_get_matches_from(["hatch", "--shell", "sleep 10"]);
+            assert!(without_name.is_err());
+
+            let with_file = get_hatch_subcommand()
+                .try_get_matches_from(["hatch", "api", "--shell", "sleep 10", "--name", "x"]);
+            assert!(with_file.is_err());
+        }
+
+        #[test]
+        fn limit_args_should_exist_for_every_resource() {
+            for resource in crate::limits::LimitResource::ALL {
+                let arg_name = resource.get_arg_name();
+                let long_flag = format!("--{}", arg_name);
+                let matches = get_hatch_subcommand()
+                    .try_get_matches_from(["hatch", "api", &long_flag, "1024"])
+                    .expect("hatch args should be valid");
+
+                assert_eq!(matches.value_of(arg_name), Some("1024"));
+            }
+        }
+
+        #[test]
+        fn env_args_should_accept_multiple_occurrences() {
+            let matches = get_hatch_subcommand()
+                .try_get_matches_from([
+                    "hatch",
+                    "api",
+                    "-e",
+                    "PORT=8000",
+                    "--env",
+                    "RUST_LOG=info",
+                    "--clear-env",
+                    "--keep-env",
+                    "PATH",
+                ])
+                .expect("hatch args should be valid");
+
+            let env: Vec<&str> = matches.values_of("env").unwrap().collect();
+            assert_eq!(env, vec!["PORT=8000", "RUST_LOG=info"]);
+            assert_eq!(matches.value_of("keep-env"), Some("PATH"));
+        }
+
+        #[test]
+        fn keep_env_should_require_clear_env() {
+            let result =
+                get_hatch_subcommand().try_get_matches_from(["hatch", "api", "--keep-env", "PATH"]);
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn only_one_health_probe_should_be_accepted() {
+            let result = get_hatch_subcommand().try_get_matches_from([
+                "hatch",
+                "api",
+                "--health-tcp",
+                "8000",
+                "--health-cmd",
+                "true",
+            ]);
+            assert!(result.is_err());
+
+            let matches = get_hatch_subcommand()
+                .try_get_matches_from(["hatch", "api", "--health-tcp", "8000"])
+                .expect("hatch args should be valid");
+            assert_eq!(matches.value_of("health-interval"), Some("30"));
+            assert_eq!(matches.value_of("health-retries"), Some("3"));
+        }
     }
 
     mod clear_subcommand {
@@ -140,9 +1209,7 @@ mod tests {
             let expected_arg_about = "don't stop any processes, just clear the tracking list";
             let arg = command
                 .get_arguments()
-                .into_iter()
-                .filter(|x| x.get_name() == expected_arg_name)
-                .next()
+                .find(|x| x.get_name() == expected_arg_name)
                 .expect("arg iterator should return valid argument");
 
             assert_eq!(arg.get_name(), expected_arg_name);
@@ -150,6 +1217,245 @@ mod tests {
         }
     }
 
+    mod namespaces_subcommand {
+        use super::get_namespaces_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "namespaces";
+            let expected_about = "list all namespaces and how many processes each is tracking";
+            utils::test_subcommand_should_return_app_instance(
+                get_namespaces_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+    }
+
+    mod where_subcommand {
+        use super::get_where_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "where";
+            let expected_about = "show which state file is in use and why";
+            utils::test_subcommand_should_return_app_instance(
+                get_where_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+    }
+
+    mod export_subcommand {
+        use super::get_export_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "export";
+            let expected_about =
+                "write the launch specs of all tracked processes to a portable file";
+            utils::test_subcommand_should_return_app_instance(
+                get_export_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+    }
+
+    mod import_subcommand {
+        use super::get_import_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "import";
+            let expected_about = "hatch all of the processes in an exported file";
+            utils::test_subcommand_should_return_app_instance(
+                get_import_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+
+        #[test]
+        fn on_conflict_should_default_to_fail() {
+            let matches = get_import_subcommand()
+                .try_get_matches_from(["import", "backup.json"])
+                .expect("import args should be valid");
+
+            assert_eq!(matches.value_of("on-conflict"), Some("fail"));
+            assert!(!matches.is_present("dry-run"));
+        }
+    }
+
+    mod up_subcommand {
+        use super::get_up_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "up";
+            let expected_about =
+                "hatch the processes declared in a manifest that aren't running yet";
+            utils::test_subcommand_should_return_app_instance(
+                get_up_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+
+        #[test]
+        fn names_should_be_optional_and_file_should_default() {
+            let matches = get_up_subcommand()
+                .try_get_matches_from(["up"])
+                .expect("up args should be valid");
+
+            assert!(matches.values_of("names").is_none());
+            assert_eq!(matches.value_of("file"), Some("Eggfile.toml"));
+        }
+
+        #[test]
+        fn multiple_names_should_be_accepted() {
+            let matches = get_up_subcommand()
+                .try_get_matches_from(["up", "web", "worker", "-f", "dev.toml"])
+                .expect("up args should be valid");
+
+            let names: Vec<&str> = matches.values_of("names").unwrap().collect();
+            assert_eq!(names, vec!["web", "worker"]);
+            assert_eq!(matches.value_of("file"), Some("dev.toml"));
+        }
+
+        #[test]
+        fn formation_should_require_procfile() {
+            let result = get_up_subcommand().try_get_matches_from(["up", "--formation", "web=2"]);
+            assert!(result.is_err());
+
+            let matches = get_up_subcommand()
+                .try_get_matches_from(["up", "--procfile", "Procfile", "--formation", "web=2"])
+                .expect("up args should be valid");
+            assert_eq!(matches.value_of("procfile"), Some("Procfile"));
+            assert_eq!(matches.value_of("formation"), Some("web=2"));
+        }
+    }
+
+    mod down_subcommand {
+        use super::get_down_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "down";
+            let expected_about = "stop the processes declared in a manifest";
+            utils::test_subcommand_should_return_app_instance(
+                get_down_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+    }
+
+    mod plan_subcommand {
+        use super::get_plan_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "plan";
+            let expected_about =
+                "show what apply would start, stop and restart to match a manifest";
+            utils::test_subcommand_should_return_app_instance(
+                get_plan_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+
+        #[test]
+        fn file_should_default_to_eggfile() {
+            let matches = get_plan_subcommand()
+                .try_get_matches_from(["plan"])
+                .expect("plan args should be valid");
+
+            assert_eq!(matches.value_of("file"), Some("Eggfile.toml"));
+        }
+    }
+
+    mod apply_subcommand {
+        use super::get_apply_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "apply";
+            let expected_about = "start, stop and restart processes until they match a manifest";
+            utils::test_subcommand_should_return_app_instance(
+                get_apply_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+    }
+
+    mod check_subcommand {
+        use super::get_check_subcommand;
+        use super::subcommand_testing_utils as utils;
+
+        #[test]
+        fn subcommand_should_return_app_instance() {
+            let expected_name = "check";
+            let expected_about = "run the health checks of tracked processes";
+            utils::test_subcommand_should_return_app_instance(
+                get_check_subcommand,
+                expected_name,
+                expected_about,
+            );
+        }
+    }
+
+    #[test]
+    fn namespace_arg_should_be_global() {
+        let arg = get_namespace_arg();
+        assert_eq!(arg.get_short(), Some('N'));
+        assert_eq!(arg.get_long(), Some("namespace"));
+        assert!(arg.is_global_set());
+    }
+
+    #[test]
+    fn output_arg_should_be_global_and_only_accept_known_formats() {
+        let app = App::new("test-app")
+            .arg(get_output_arg())
+            .subcommand(get_list_processes_subcommand());
+
+        let matches = app
+            .clone()
+            .try_get_matches_from(["test-app", "list", "-o", "yaml"])
+            .expect("output arg should be accepted after a subcommand");
+        assert_eq!(matches.value_of("output"), Some("yaml"));
+
+        let result = app.try_get_matches_from(["test-app", "--output", "xml", "list"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn color_arg_should_be_global_and_only_accept_known_choices() {
+        let app = App::new("test-app")
+            .arg(get_color_arg())
+            .subcommand(get_list_processes_subcommand());
+
+        let matches = app
+            .clone()
+            .try_get_matches_from(["test-app", "list", "--color", "never"])
+            .expect("color arg should be accepted after a subcommand");
+        assert_eq!(matches.value_of("color"), Some("never"));
+
+        let result = app.try_get_matches_from(["test-app", "--color", "sometimes", "list"]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn get_all_subcommands_return_should_be_foldable_into_app() {
         let all_subcommands = get_all_subcommands();
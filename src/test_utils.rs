@@ -0,0 +1,22 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+// a unique directory under the system temp dir, removed again on drop
+pub struct TestDir {
+    pub path: PathBuf,
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _result = fs::remove_dir_all(&self.path);
+    }
+}
+
+impl TestDir {
+    pub fn new() -> Self {
+        let path = env::temp_dir().join(format!("{}.testdir", Uuid::new_v4().to_simple()));
+        Self { path }
+    }
+}
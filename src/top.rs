@@ -0,0 +1,414 @@
+use super::columns::{self, Column, ProcessRow};
+use super::file_io;
+use super::output_display;
+use super::signals;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::{self, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+// plenty for a screenful of lines, without reading through huge logs
+const LOG_TAIL_BYTES: u64 = 64 * 1024;
+const COLUMNS: [Column; 9] = [
+    Column::Name,
+    Column::Id,
+    Column::Pid,
+    Column::Status,
+    Column::Uptime,
+    Column::Cpu,
+    Column::Rss,
+    Column::Threads,
+    Column::Fds,
+];
+const HELP_TEXT: &str = "q quit  up/down select  s stop  r restart  x signal";
+
+struct TopState {
+    rows: Vec<ProcessRow>,
+    selected: usize,
+    // total CPU time of each pid at the last refresh, so CPU% covers just the time since
+    cpu_samples: HashMap<String, (f64, Instant)>,
+    message: String,
+    // the signal name being typed after pressing x
+    signal_input: Option<String>,
+}
+
+enum KeyAction {
+    Quit,
+    Redraw,
+    // runs an eggsecutor subcommand, showing the first message until it's done
+    Run {
+        args: Vec<String>,
+        running_message: String,
+        done_message: String,
+    },
+}
+
+pub fn run_top() -> io::Result<()> {
+    let mut stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return Err(io::Error::other("stdout is not a terminal"));
+    }
+
+    terminal::enable_raw_mode()?;
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = run_event_loop(&mut stdout);
+    // the terminal has to be given back even when drawing failed
+    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn run_event_loop(stdout: &mut io::Stdout) -> io::Result<()> {
+    let mut state = TopState {
+        rows: vec![],
+        selected: 0,
+        cpu_samples: HashMap::new(),
+        message: HELP_TEXT.to_string(),
+        signal_input: None,
+    };
+    state.refresh();
+    let mut refreshed_at = Instant::now();
+
+    loop {
+        draw(stdout, &state)?;
+        let timeout = REFRESH_INTERVAL.saturating_sub(refreshed_at.elapsed());
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                match state.handle_key(key) {
+                    KeyAction::Quit => return Ok(()),
+                    KeyAction::Redraw => {}
+                    KeyAction::Run {
+                        args,
+                        running_message,
+                        done_message,
+                    } => {
+                        state.message = running_message;
+                        draw(stdout, &state)?;
+                        state.message = run_subcommand(&args).unwrap_or(done_message);
+                        state.refresh();
+                        refreshed_at = Instant::now();
+                    }
+                }
+            }
+        }
+        if refreshed_at.elapsed() >= REFRESH_INTERVAL {
+            state.refresh();
+            refreshed_at = Instant::now();
+        }
+    }
+}
+
+impl TopState {
+    fn refresh(&mut self) {
+        let selected_id = self.get_selected_row().map(|x| x.process.id.clone());
+        let processes = file_io::get_running_processes_from_state_file().unwrap_or_default();
+        let mut rows = columns::get_process_rows(processes);
+
+        let now = Instant::now();
+        for row in &mut rows {
+            let previous = self.cpu_samples.get(&row.process.pid);
+            if let (Some(stats), Some(&(cpu_time_secs, sampled_at))) = (&mut row.stats, previous) {
+                stats.cpu_percent =
+                    get_cpu_percent(cpu_time_secs, stats.cpu_time_secs, now - sampled_at);
+            }
+        }
+        self.cpu_samples = rows
+            .iter()
+            .filter_map(|row| {
+                let stats = row.stats.as_ref()?;
+                Some((row.process.pid.clone(), (stats.cpu_time_secs, now)))
+            })
+            .collect();
+
+        // the selection follows its process when rows come and go
+        self.selected = selected_id
+            .and_then(|id| rows.iter().position(|x| x.process.id == id))
+            .unwrap_or_else(|| self.selected.min(rows.len().saturating_sub(1)));
+        self.rows = rows;
+    }
+
+    fn get_selected_row(&self) -> Option<&ProcessRow> {
+        self.rows.get(self.selected)
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> KeyAction {
+        if key.kind != KeyEventKind::Press {
+            return KeyAction::Redraw;
+        }
+        if self.signal_input.is_some() {
+            return self.handle_signal_input_key(key);
+        }
+
+        // raw mode turns ctrl-c into a key press instead of SIGINT
+        let is_interrupt =
+            key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c');
+        let selected = self
+            .get_selected_row()
+            .map(|x| (x.process.id.clone(), x.process.name.clone()));
+
+        match (key.code, selected) {
+            _ if is_interrupt => KeyAction::Quit,
+            (KeyCode::Char('q'), _) | (KeyCode::Esc, _) => KeyAction::Quit,
+            (KeyCode::Up, _) | (KeyCode::Char('k'), _) => {
+                self.selected = self.selected.saturating_sub(1);
+                KeyAction::Redraw
+            }
+            (KeyCode::Down, _) | (KeyCode::Char('j'), _) => {
+                self.selected = (self.selected + 1).min(self.rows.len().saturating_sub(1));
+                KeyAction::Redraw
+            }
+            (KeyCode::Char('s'), Some((id, name))) => KeyAction::Run {
+                args: vec!["stop".to_string(), id],
+                running_message: format!("stopping {}...", name),
+                done_message: format!("stopped {}", name),
+            },
+            (KeyCode::Char('r'), Some((id, name))) => KeyAction::Run {
+                args: vec!["restart".to_string(), id],
+                running_message: format!("restarting {}...", name),
+                done_message: format!("restarted {}", name),
+            },
+            (KeyCode::Char('x'), Some(_)) => {
+                self.signal_input = Some(String::new());
+                KeyAction::Redraw
+            }
+            _ => KeyAction::Redraw,
+        }
+    }
+
+    fn handle_signal_input_key(&mut self, key: KeyEvent) -> KeyAction {
+        let input = self.signal_input.get_or_insert_with(String::new);
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => self.signal_input = None,
+            KeyCode::Enter => {
+                let input = self.signal_input.take().unwrap_or_default();
+                let row = match self.get_selected_row() {
+                    Some(row) => row,
+                    None => return KeyAction::Redraw,
+                };
+                match signals::normalize_signal_name(&input) {
+                    Some(signal) => {
+                        return KeyAction::Run {
+                            running_message: format!(
+                                "sending SIG{} to {}...",
+                                signal, row.process.name
+                            ),
+                            done_message: format!("sent SIG{} to {}", signal, row.process.name),
+                            args: vec!["signal".to_string(), row.process.id.clone(), signal],
+                        }
+                    }
+                    None => self.message = format!(r#"unknown signal "{}""#, input),
+                }
+            }
+            _ => {}
+        }
+        KeyAction::Redraw
+    }
+}
+
+// runs in its own process, so that an error exiting it can't take the screen with it,
+// returns the error message if it failed
+fn run_subcommand(args: &[String]) -> Option<String> {
+    let output = env::current_exe().and_then(|exe| {
+        Command::new(exe)
+            .args(["--output", "json"])
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+    });
+    match output {
+        Ok(output) if output.status.success() => None,
+        Ok(output) => Some(get_subcommand_error_message(&output.stderr)),
+        Err(err) => Some(format!("error: {}", err)),
+    }
+}
+
+// errors come as {"error": {"code": ..., "message": ...}} with --output json
+fn get_subcommand_error_message(stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    let message = serde_json::from_str::<serde_json::Value>(stderr.trim())
+        .ok()
+        .and_then(|x| x["error"]["message"].as_str().map(|x| x.to_string()))
+        .or_else(|| {
+            stderr
+                .lines()
+                .rev()
+                .find(|x| !x.trim().is_empty())
+                .map(|x| x.to_string())
+        })
+        .unwrap_or_else(|| "failed".to_string());
+    format!("error: {}", message)
+}
+
+fn draw(stdout: &mut io::Stdout, state: &TopState) -> io::Result<()> {
+    let (width, height) = terminal::size()?;
+    let (width, height) = (width as usize, height as usize);
+    let widths = output_display::get_column_widths(&state.rows, &COLUMNS, Some(width));
+    let mut lines: Vec<(String, Option<Attribute>)> = vec![
+        (
+            format!(
+                "eggsecutor top - namespace: {} - {} {}",
+                file_io::get_current_namespace(),
+                state.rows.len(),
+                match state.rows.len() {
+                    1 => "process",
+                    _ => "processes",
+                }
+            ),
+            Some(Attribute::Bold),
+        ),
+        (
+            output_display::get_display_header_row_string(&COLUMNS, &widths),
+            Some(Attribute::Reverse),
+        ),
+    ];
+
+    // the table gets the top half, the log of the selected process the rest
+    let table_height = (height.saturating_sub(4) / 2).max(1);
+    let (start, end) = get_visible_range(state.selected, state.rows.len(), table_height);
+    for index in start..end {
+        // colors would get in the way of the selection's reverse video
+        let row = output_display::get_display_process_row_string(
+            &state.rows[index],
+            &COLUMNS,
+            &widths,
+            false,
+        );
+        let attribute = match index == state.selected {
+            true => Some(Attribute::Reverse),
+            false => None,
+        };
+        lines.push((row, attribute));
+    }
+    lines.resize(table_height + 2, (String::new(), None));
+
+    let log_file = state
+        .get_selected_row()
+        .and_then(|x| x.process.log_file.clone());
+    let log_title = match &log_file {
+        Some(log_file) => format!("-- log: {} ", log_file),
+        None => String::new(),
+    };
+    lines.push((format!("{:-<width$}", log_title, width = width), None));
+    let log_height = height.saturating_sub(lines.len() + 1);
+    let log_lines = log_file
+        .map(|x| read_log_tail(&x, log_height))
+        .unwrap_or_default();
+    lines.extend(log_lines.into_iter().map(|x| (x, None)));
+    lines.resize(height.saturating_sub(1), (String::new(), None));
+
+    let footer = match &state.signal_input {
+        Some(input) => format!("signal to send (enter to send, esc to cancel): {}", input),
+        None => state.message.clone(),
+    };
+    lines.push((footer, None));
+
+    for (y, (line, attribute)) in lines.iter().enumerate().take(height) {
+        let line: String = line.chars().take(width).collect();
+        queue!(stdout, cursor::MoveTo(0, y as u16))?;
+        match attribute {
+            Some(attribute) => queue!(
+                stdout,
+                SetAttribute(*attribute),
+                Print(format!("{:<width$}", line, width = width)),
+                SetAttribute(Attribute::Reset)
+            )?,
+            None => queue!(stdout, Print(line))?,
+        }
+        queue!(stdout, terminal::Clear(ClearType::UntilNewLine))?;
+    }
+    stdout.flush()
+}
+
+// (first, last + 1) of the rows to show, scrolled so the selected one is on screen
+fn get_visible_range(selected: usize, row_count: usize, height: usize) -> (usize, usize) {
+    let start = (selected + 1).saturating_sub(height);
+    (start, row_count.min(start + height))
+}
+
+fn get_cpu_percent(previous_cpu_secs: f64, cpu_secs: f64, elapsed: Duration) -> f64 {
+    match elapsed.as_secs_f64() {
+        secs if secs > 0.0 => (cpu_secs - previous_cpu_secs).max(0.0) / secs * 100.0,
+        _ => 0.0,
+    }
+}
+
+fn read_log_tail(path: &str, count: usize) -> Vec<String> {
+    let read_tail = || -> io::Result<(String, bool)> {
+        let mut file = File::open(path)?;
+        let length = file.metadata()?.len();
+        let start = length.saturating_sub(LOG_TAIL_BYTES);
+        file.seek(SeekFrom::Start(start))?;
+        let mut content = vec![];
+        file.read_to_end(&mut content)?;
+        Ok((String::from_utf8_lossy(&content).to_string(), start > 0))
+    };
+    match read_tail() {
+        Ok((content, is_cut)) => get_last_lines(&content, count, is_cut),
+        Err(err) => vec![format!("couldn't read the log: {}", err)],
+    }
+}
+
+// a tail that starts mid-file drops its first line, which is likely cut off
+fn get_last_lines(content: &str, count: usize, is_cut: bool) -> Vec<String> {
+    let lines: Vec<&str> = content.lines().skip(is_cut as usize).collect();
+    lines[lines.len().saturating_sub(count)..]
+        .iter()
+        // control characters would move the cursor around the screen
+        .map(|x| {
+            x.replace('\t', "    ")
+                .chars()
+                .filter(|c| !c.is_control())
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_range_should_scroll_to_selected_row() {
+        assert_eq!(get_visible_range(0, 3, 10), (0, 3));
+        assert_eq!(get_visible_range(4, 20, 5), (0, 5));
+        assert_eq!(get_visible_range(5, 20, 5), (1, 6));
+        assert_eq!(get_visible_range(19, 20, 5), (15, 20));
+    }
+
+    #[test]
+    fn cpu_percent_should_cover_time_since_last_sample() {
+        let percent = get_cpu_percent(1.0, 1.5, Duration::from_secs(2));
+        assert!((percent - 25.0).abs() < f64::EPSILON);
+        assert_eq!(get_cpu_percent(1.0, 1.5, Duration::from_secs(0)), 0.0);
+    }
+
+    #[test]
+    fn last_lines_should_skip_cut_line_and_control_characters() {
+        let content = "ial line\nfirst\nsecond\x1b[0m\n\tthird\n";
+
+        assert_eq!(get_last_lines(content, 2, true), ["second[0m", "    third"]);
+        assert_eq!(get_last_lines(content, 10, true).len(), 3);
+        assert_eq!(get_last_lines(content, 10, false).len(), 4);
+    }
+
+    #[test]
+    fn subcommand_error_message_should_come_from_json_error() {
+        let stderr = br#"{"error": {"code": "no_such_process", "message": "no such process"}}"#;
+        assert_eq!(
+            get_subcommand_error_message(stderr),
+            "error: no such process"
+        );
+        assert_eq!(get_subcommand_error_message(b"boom\n"), "error: boom");
+    }
+}
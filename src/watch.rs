@@ -0,0 +1,160 @@
+use super::columns::ProcessRow;
+use super::file_io;
+use super::output_display;
+use super::{get_sorted_process_rows, is_process_alive, ListOptions, ProcessInfo, ProcessStatus};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, queue};
+use std::io::{self, IsTerminal, Write};
+use std::thread;
+use std::time::Duration;
+
+// what `list --watch --exit-on-death` exits with once a process dies
+pub const DEATH_EXIT_CODE: i32 = 1;
+
+pub struct WatchOptions {
+    pub interval: Duration,
+    pub exit_on_death: bool,
+}
+
+// returns only once a process dies with `exit_on_death`, otherwise runs until interrupted
+pub fn watch_processes(options: &ListOptions, watch: &WatchOptions) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let is_redrawn = stdout.is_terminal() && !output_display::is_structured_output();
+    let mut previous_rows: Option<Vec<ProcessRow>> = None;
+
+    loop {
+        let rows = get_watched_rows(previous_rows.as_deref(), options)?;
+        let changed_ids = match &previous_rows {
+            Some(previous_rows) => get_changed_process_ids(previous_rows, &rows),
+            None => vec![],
+        };
+
+        if output_display::is_structured_output() {
+            output_display::print_structured_output_document(&rows);
+        } else if is_redrawn {
+            let table = output_display::get_display_output_str_for_watched_processes(
+                &rows,
+                &options.columns,
+                &changed_ids,
+            );
+            redraw(&mut stdout, &table, watch.interval)?;
+        } else {
+            println!(
+                "{}",
+                output_display::get_display_output_str_for_processes(&rows, &options.columns)
+            );
+        }
+
+        let died: Vec<&ProcessInfo> = rows
+            .iter()
+            .map(|x| &x.process)
+            .filter(|x| x.status == ProcessStatus::Exited && changed_ids.contains(&x.id))
+            .collect();
+        died.iter()
+            .for_each(|x| output_display::print_process_died_message(&x.name, &x.pid));
+        if watch.exit_on_death && !died.is_empty() {
+            return Ok(());
+        }
+
+        previous_rows = Some(rows);
+        thread::sleep(watch.interval);
+    }
+}
+
+// the running processes, plus the ones that died since watching started, for as
+// long as they stay tracked
+fn get_watched_rows(
+    previous_rows: Option<&[ProcessRow]>,
+    options: &ListOptions,
+) -> io::Result<Vec<ProcessRow>> {
+    let tracked = file_io::get_all_processes_from_state_file().unwrap_or_default();
+    let mut processes = vec![];
+    for mut process in tracked {
+        let was_watched = previous_rows
+            .unwrap_or_default()
+            .iter()
+            .any(|x| x.process.id == process.id && x.process.pid == process.pid);
+        if is_process_alive(&process.pid)? {
+            processes.push(process);
+        } else if was_watched {
+            process.status = ProcessStatus::Exited;
+            processes.push(process);
+        }
+    }
+    Ok(get_sorted_process_rows(processes, options))
+}
+
+// a restart keeps the id, so a new pid counts as a change too
+fn get_changed_process_ids(previous_rows: &[ProcessRow], rows: &[ProcessRow]) -> Vec<String> {
+    rows.iter()
+        .filter(|row| {
+            let previous = previous_rows
+                .iter()
+                .find(|x| x.process.id == row.process.id);
+            match previous {
+                Some(previous) => {
+                    previous.process.status != row.process.status
+                        || previous.process.pid != row.process.pid
+                }
+                None => true,
+            }
+        })
+        .map(|row| row.process.id.clone())
+        .collect()
+}
+
+fn redraw(stdout: &mut io::Stdout, table: &str, interval: Duration) -> io::Result<()> {
+    queue!(stdout, cursor::MoveTo(0, 0))?;
+    let title = format!(
+        "every {}: eggsecutor list",
+        humantime::format_duration(interval)
+    );
+    for line in [title.as_str(), ""].iter().copied().chain(table.lines()) {
+        write!(stdout, "{}", line)?;
+        queue!(stdout, terminal::Clear(ClearType::UntilNewLine))?;
+        writeln!(stdout)?;
+    }
+    queue!(stdout, terminal::Clear(ClearType::FromCursorDown))?;
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_ids_should_include_new_dead_and_restarted_processes() {
+        let previous_rows = vec![
+            get_test_row("aaaa", "1", ProcessStatus::Running),
+            get_test_row("bbbb", "2", ProcessStatus::Running),
+            get_test_row("cccc", "3", ProcessStatus::Running),
+        ];
+        let rows = vec![
+            get_test_row("aaaa", "1", ProcessStatus::Running),
+            get_test_row("bbbb", "2", ProcessStatus::Exited),
+            get_test_row("cccc", "30", ProcessStatus::Running),
+            get_test_row("dddd", "4", ProcessStatus::Running),
+        ];
+
+        assert_eq!(
+            get_changed_process_ids(&previous_rows, &rows),
+            ["bbbb", "cccc", "dddd"]
+        );
+    }
+
+    fn get_test_row(id: &str, pid: &str, status: ProcessStatus) -> ProcessRow {
+        let mut process: ProcessInfo = serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": "web",
+            "pid": pid,
+            "status": "Running",
+        }))
+        .unwrap();
+        process.status = status;
+        ProcessRow {
+            process,
+            stats: None,
+            cgroup_usage: None,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+const APP_DIR_NAME: &str = "eggsecutor";
+
+pub fn get_state_dir() -> PathBuf {
+    resolve_base_dir(env::var_os("XDG_STATE_HOME"), "~/.local/state").join(APP_DIR_NAME)
+}
+
+pub fn get_config_dir() -> PathBuf {
+    resolve_base_dir(env::var_os("XDG_CONFIG_HOME"), "~/.config").join(APP_DIR_NAME)
+}
+
+// the spec has no default for the runtime dir, so fall back to the system temp dir
+pub fn get_runtime_dir() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from) {
+        Some(runtime_dir) if runtime_dir.is_absolute() => runtime_dir.join(APP_DIR_NAME),
+        _ => env::temp_dir().join(format!("{}-{}", APP_DIR_NAME, get_user_name())),
+    }
+}
+
+// relative paths in XDG variables are invalid per the spec and must be ignored
+fn resolve_base_dir(env_value: Option<OsString>, default_dir: &str) -> PathBuf {
+    match env_value.map(PathBuf::from) {
+        Some(base_dir) if base_dir.is_absolute() => base_dir,
+        _ => PathBuf::from(shellexpand::tilde(default_dir).to_string()),
+    }
+}
+
+fn get_user_name() -> String {
+    env::var("USER").unwrap_or_else(|_| "user".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn absolute_env_value_should_be_used_as_base_dir() {
+        let base_dir = resolve_base_dir(Some(OsString::from("/var/state")), "~/.local/state");
+        assert_eq!(base_dir, PathBuf::from("/var/state"));
+    }
+
+    #[test]
+    fn missing_env_value_should_fall_back_to_default_dir() {
+        let base_dir = resolve_base_dir(None, "~/.local/state");
+        let expected_dir = PathBuf::from(shellexpand::tilde("~/.local/state").to_string());
+        assert_eq!(base_dir, expected_dir);
+    }
+
+    #[test]
+    fn relative_env_value_should_be_ignored() {
+        let base_dir = resolve_base_dir(Some(OsString::from("state")), "~/.local/state");
+        let expected_dir = PathBuf::from(shellexpand::tilde("~/.local/state").to_string());
+        assert_eq!(base_dir, expected_dir);
+    }
+
+    #[test]
+    fn app_dirs_should_be_namespaced_under_app_name() {
+        assert!(get_state_dir().ends_with(APP_DIR_NAME));
+        assert!(get_config_dir().ends_with(APP_DIR_NAME));
+        assert!(get_runtime_dir().is_absolute());
+    }
+}